@@ -1,29 +1,185 @@
 use std::env;
 use std::path::PathBuf;
-use std::process::Command;
+#[cfg(not(any(feature = "dynamic", feature = "prebuilt")))]
+use std::{
+    fs,
+    panic::{self, AssertUnwindSafe},
+    process::{Command, Stdio},
+};
 
 fn main() {
+    #[cfg(feature = "dynamic")]
+    generate_bindings(link_system_ryzenadj());
+    #[cfg(all(feature = "prebuilt", not(feature = "dynamic")))]
+    link_prebuilt();
+    #[cfg(not(any(feature = "dynamic", feature = "prebuilt")))]
+    build_bundled();
+}
+
+// Links the static libryzenadj archive RYZENADJ_STATIC_LIB points at, skipping the CMake build
+#[cfg(all(feature = "prebuilt", not(feature = "dynamic")))]
+fn link_prebuilt() {
+    println!("cargo:rerun-if-env-changed=RYZENADJ_STATIC_LIB");
+    let lib = env::var_os("RYZENADJ_STATIC_LIB").map(PathBuf::from).unwrap_or_else(|| {
+        panic!("the prebuilt feature needs RYZENADJ_STATIC_LIB set to the path of a prebuilt libryzenadj.a")
+    });
+    let lib = match std::fs::canonicalize(&lib) {
+        Ok(path) if path.is_file() => path,
+        _ => panic!(
+            "RYZENADJ_STATIC_LIB={} does not point at an existing file",
+            lib.display()
+        ),
+    };
+    let name = lib
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.strip_prefix("lib"))
+        .and_then(|name| name.strip_suffix(".a"))
+        .unwrap_or_else(|| {
+            panic!(
+                "RYZENADJ_STATIC_LIB={} is not a static archive named lib<name>.a",
+                lib.display()
+            )
+        });
+    println!("cargo:rerun-if-changed={}", lib.display());
+    // canonical file paths always have a parent
+    let dir = lib
+        .parent()
+        .expect("RYZENADJ_STATIC_LIB has no parent directory");
+    println!("cargo:rustc-link-search=native={}", dir.display());
+    println!("cargo:rustc-link-lib=static={name}");
+    link_pci();
+    generate_bindings(None);
+}
+
+// Builds the RyzenAdj submodule with CMake and links it statically
+#[cfg(not(any(feature = "dynamic", feature = "prebuilt")))]
+fn build_bundled() {
     // Remove CMAKE_INTERPROCEDURAL_OPTIMIZATION option to prevent linking errors
     Command::new("sed").args(["-i", "s/set(CMAKE_INTERPROCEDURAL_OPTIMIZATION TRUE)/set(CMAKE_INTERPROCEDURAL_OPTIMIZATION FALSE)/g", "./RyzenAdj/CMakeLists.txt"])
         .status()
         .expect("Failed to update CMakeLists.txt");
 
     if env::var("DOCS_RS").unwrap_or_else(|_| "0".to_string()) == "0" {
-        let dst = cmake::Config::new("RyzenAdj")
-            .define("BUILD_SHARED_LIBS", "OFF")
-            .profile("Release")
-            .build_target("libryzenadj")
-            .build();
+        // keep debug symbols in the native library when asked for, helps when debugging crashes in ryzenadj
+        let profile = if env::var("CARGO_FEATURE_RYZENADJ_DEBUG").is_ok() {
+            "RelWithDebInfo"
+        } else {
+            "Release"
+        };
+        let dst = panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut config = cmake::Config::new("RyzenAdj");
+            config
+                .define("BUILD_SHARED_LIBS", "OFF")
+                .profile(profile)
+                .build_target("libryzenadj");
+            configure_cmake(&mut config);
+            config.build()
+        }))
+        .unwrap_or_else(|_| {
+            diagnose_build_failure();
+            panic!("Failed to build RyzenAdj with CMake, see the warnings above and the CMake output in the build log");
+        });
         //panic!("dst: {:?}", dst.display());
         println!("cargo:rustc-link-search=native={}/build", dst.display());
         println!("cargo:rustc-link-lib=static=ryzenadj");
-        println!("cargo:rustc-link-lib=dylib=pci");
+        link_pci();
     }
-    println!("cargo:rerun-if-changed=wrapper.h");
+    generate_bindings(None);
+}
 
-    let bindings = bindgen::Builder::default()
+// Applies the CMake generator and number of parallel build jobs from the environment, if set
+#[cfg(not(any(feature = "dynamic", feature = "prebuilt")))]
+fn configure_cmake(config: &mut cmake::Config) {
+    println!("cargo:rerun-if-env-changed=RYZENADJ_CMAKE_GENERATOR");
+    println!("cargo:rerun-if-env-changed=RYZENADJ_BUILD_JOBS");
+    if let Some(generator) = env::var_os("RYZENADJ_CMAKE_GENERATOR") {
+        config.generator(generator);
+    }
+    if let Ok(jobs) = env::var("RYZENADJ_BUILD_JOBS") {
+        match jobs.parse::<u32>() {
+            // make and ninja both take -j, passed after `--` to the native build tool
+            Ok(jobs) if jobs > 0 => {
+                config.build_arg(format!("-j{jobs}"));
+            }
+            _ => println!(
+                "cargo:warning=ignoring RYZENADJ_BUILD_JOBS={jobs}, expected a positive number"
+            ),
+        }
+    }
+}
+
+// Links libpci, which libryzenadj depends on, statically with the static-pci feature
+#[cfg(not(feature = "dynamic"))]
+fn link_pci() {
+    let statik = env::var("CARGO_FEATURE_STATIC_PCI").is_ok();
+    if !statik {
+        // pkg-config knows where libpci lives on non-FHS systems like NixOS
+        if pkg_config::probe_library("libpci").is_err() {
+            println!("cargo:rustc-link-lib=dylib=pci");
+        }
+        return;
+    }
+    // pkg-config-rs never links libraries under /usr statically, so only ask it for the search paths
+    // and the libraries a static libpci needs, and emit the links here
+    match pkg_config::Config::new()
+        .statik(true)
+        .cargo_metadata(false)
+        .probe("libpci")
+    {
+        Ok(lib) => {
+            for path in &lib.link_paths {
+                println!("cargo:rustc-link-search=native={}", path.display());
+            }
+            println!("cargo:rustc-link-lib=static=pci");
+            for dep in lib.libs.iter().filter(|name| *name != "pci") {
+                println!("cargo:rustc-link-lib={dep}");
+            }
+        }
+        Err(_) => println!("cargo:rustc-link-lib=static=pci"),
+    }
+}
+
+// Links against an already installed ryzenadj, returns the directory holding ryzenadj.h if known
+#[cfg(feature = "dynamic")]
+fn link_system_ryzenadj() -> Option<PathBuf> {
+    println!("cargo:rerun-if-env-changed=RYZENADJ_LIB_DIR");
+    println!("cargo:rerun-if-env-changed=RYZENADJ_INCLUDE_DIR");
+    let include_dir = env::var_os("RYZENADJ_INCLUDE_DIR").map(PathBuf::from);
+
+    if let Some(lib_dir) = env::var_os("RYZENADJ_LIB_DIR").map(PathBuf::from) {
+        println!("cargo:rustc-link-search=native={}", lib_dir.display());
+        println!("cargo:rustc-link-lib=dylib=ryzenadj");
+        return include_dir;
+    }
+
+    match pkg_config::Config::new().probe("ryzenadj") {
+        Ok(library) => include_dir.or_else(|| library.include_paths.into_iter().next()),
+        Err(_) => {
+            println!("cargo:warning=ryzenadj was not found, set RYZENADJ_LIB_DIR and RYZENADJ_INCLUDE_DIR or install a ryzenadj.pc for pkg-config");
+            println!("cargo:rustc-link-lib=dylib=ryzenadj");
+            include_dir
+        }
+    }
+}
+
+// Generates bindings from ryzenadj.h in `include_dir`, or from the RyzenAdj submodule if it is `None`
+fn generate_bindings(include_dir: Option<PathBuf>) {
+    let builder = match include_dir {
+        Some(include_dir) => bindgen::Builder::default()
+            .header_contents(
+                "wrapper.h",
+                "#include <stddef.h>\n#include <stdint.h>\n#include \"ryzenadj.h\"\n",
+            )
+            .clang_arg(format!("-I{}", include_dir.display())),
+        None => {
+            println!("cargo:rerun-if-changed=wrapper.h");
+            bindgen::Builder::default().header("wrapper.h")
+        }
+    };
+
+    let bindings = builder
         //.default_enum_style(EnumVariation::NewType { is_bitfield: false })
-        .header("wrapper.h")
         .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()))
         .generate()
         .expect("Unable to generate bindings");
@@ -32,4 +188,47 @@ fn main() {
     bindings
         .write_to_file(out_path.join("bindings.rs"))
         .expect("Couldn't write bindings!");
+
+    // names of the bound functions, lets the safe crate check that it wraps all of them
+    let names: String = bindings
+        .to_string()
+        .lines()
+        .filter_map(|line| line.trim_start().strip_prefix("pub fn "))
+        .filter_map(|rest| rest.split('(').next())
+        .map(|name| format!("    {name:?},\n"))
+        .collect();
+    std::fs::write(
+        out_path.join("functions.rs"),
+        format!("/// Names of all functions bound from ryzenadj.h\n#[doc(hidden)]\npub const FUNCTIONS: &[&str] = &[\n{names}];\n"),
+    )
+    .expect("Couldn't write function names!");
+}
+
+// Emits warnings with remediation for the usual reasons the CMake build fails on a fresh system
+#[cfg(not(any(feature = "dynamic", feature = "prebuilt")))]
+fn diagnose_build_failure() {
+    if Command::new("cmake").arg("--version").output().is_err() {
+        println!("cargo:warning=cmake was not found, install it with your package manager (e.g. `apt install cmake`)");
+    }
+    if !has_pci_header() {
+        println!("cargo:warning=pci/pci.h was not found, install the libpci development package (e.g. `apt install libpci-dev` or `dnf install pciutils-devel`)");
+    }
+}
+
+// Checks if the C compiler can find pci/pci.h by running only its preprocessor
+#[cfg(not(any(feature = "dynamic", feature = "prebuilt")))]
+fn has_pci_header() -> bool {
+    let probe = PathBuf::from(env::var("OUT_DIR").unwrap()).join("pci_probe.c");
+    if fs::write(&probe, "#include <pci/pci.h>\n").is_err() {
+        return true;
+    }
+    let cc = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    Command::new(cc)
+        .arg("-E")
+        .arg(&probe)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(true)
 }
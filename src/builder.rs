@@ -0,0 +1,288 @@
+use std::cell::Cell;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use errno::{errno, Errno};
+
+use crate::lock::{self, SmuLock};
+use crate::{limits, ParamRange, PowerScale, RyzenAdj, RyzenAdjError, RyzenAdjResult, RyzenFamily};
+
+const EPERM: i32 = 1;
+const ENOENT: i32 = 2;
+const EACCES: i32 = 13;
+const ENODEV: i32 = 19;
+
+/// Number of live instances in this process
+static OPEN_INSTANCES: AtomicUsize = AtomicUsize::new(0);
+
+/// Serializes the tests depending on [`OPEN_INSTANCES`]
+#[cfg(test)]
+pub(crate) fn lock_instances() -> std::sync::MutexGuard<'static, ()> {
+    static INSTANCES: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    INSTANCES.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Marks an instance as open, failing if another one is live unless `multi_open` is set
+pub(crate) fn acquire(multi_open: bool) -> RyzenAdjResult<()> {
+    if multi_open {
+        OPEN_INSTANCES.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    } else {
+        OPEN_INSTANCES
+            .compare_exchange(0, 1, Ordering::SeqCst, Ordering::SeqCst)
+            .map(|_| ())
+            .map_err(|_| RyzenAdjError::AlreadyOpen)
+    }
+}
+
+/// Marks an instance as closed, called when it is dropped or its handle is released
+pub(crate) fn release() {
+    OPEN_INSTANCES.fetch_sub(1, Ordering::SeqCst);
+}
+
+/// Maps the errno left by a failed init to the matching error variant
+fn init_error(errno: Errno) -> RyzenAdjError {
+    match errno.0 {
+        EPERM | EACCES => RyzenAdjError::PermissionDenied,
+        ENOENT | ENODEV => RyzenAdjError::DriverNotFound,
+        _ => RyzenAdjError::InitError { errno },
+    }
+}
+
+/// How getters of a [`RyzenAdj`] instance report values the CPU returned as NaN
+///
+/// NaN usually means the value is not reported by this CPU family
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NanPolicy {
+    /// getters and [`RyzenAdj::get_opt`] return [`RyzenAdjError::GetNaN`]
+    Error,
+    /// getters return `0.0`, so unavailable values can not be told apart from real zeros
+    Zero,
+    /// [`RyzenAdj::get_opt`] returns `None`, the `f32` getters can not and return [`RyzenAdjError::GetNaN`]
+    #[default]
+    None,
+}
+
+/// Default minimum time between two refreshes triggered by [`RyzenAdjBuilder::auto_refresh`]
+pub const AUTO_REFRESH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Configures and opens a [`RyzenAdj`] instance, returned by [`RyzenAdj::builder`]
+#[derive(Debug, Clone, Default)]
+pub struct RyzenAdjBuilder {
+    nan_policy: NanPolicy,
+    multi_open: bool,
+    smu_lock: Option<SmuLock>,
+    auto_refresh: bool,
+    auto_refresh_interval: Option<Duration>,
+    power_scale: Option<PowerScale>,
+    max_curve_offset: Option<u32>,
+    assume_family: Option<RyzenFamily>,
+}
+
+impl RyzenAdjBuilder {
+    /// Returns a builder with the same defaults as [`RyzenAdj::new`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how getters report NaN values, defaults to [`NanPolicy::None`]
+    pub fn nan_policy(mut self, nan_policy: NanPolicy) -> Self {
+        self.nan_policy = nan_policy;
+        self
+    }
+
+    /// Allows opening this instance while others are live in the process, defaults to `false`
+    ///
+    /// By default only one instance may be open at a time, as instances do not coordinate their
+    /// SMU access and tracked state like [`RyzenAdj::is_oc_enabled`] is per instance. Opening
+    /// another one fails with [`RyzenAdjError::AlreadyOpen`]. With this set the instance is opened
+    /// regardless, for advanced cases like a dedicated monitoring thread.
+    pub fn multi_open(mut self, multi_open: bool) -> Self {
+        self.multi_open = multi_open;
+        self
+    }
+
+    /// Holds an advisory lock on [`SMU_LOCK_FILE`] in [`SMU_LOCK_DIR`] around every `set_*` and `refresh`, disabled by default
+    ///
+    /// If the lock is held by another process for longer than `timeout`, the call fails with
+    /// [`RyzenAdjError::SmuBusy`], if the lockfile can not be opened with [`RyzenAdjError::Io`].
+    /// This only coordinates with tools locking the same file with `flock`, the ryzenadj CLI and vendor
+    /// software ignore it. Without [`SMU_LOCK_DIR`] the lockfile is created in the temp dir. The lock
+    /// is only implemented on unix, elsewhere every locked call fails with [`RyzenAdjError::Io`].
+    ///
+    /// Any local user can create and hold the lockfile in a world writable directory, blocking
+    /// every SMU access of the tools using it. Use [`RyzenAdjBuilder::smu_lock_path`] with a
+    /// directory only trusted users can write to where that matters.
+    ///
+    /// [`SMU_LOCK_FILE`]: crate::SMU_LOCK_FILE
+    /// [`SMU_LOCK_DIR`]: crate::SMU_LOCK_DIR
+    pub fn smu_lock(self, timeout: Duration) -> Self {
+        self.smu_lock_path(lock::default_lock_path(), timeout)
+    }
+
+    /// Like [`RyzenAdjBuilder::smu_lock`], but locks the lockfile at `path`, which is created if missing
+    pub fn smu_lock_path(mut self, path: impl Into<PathBuf>, timeout: Duration) -> Self {
+        self.smu_lock = Some(SmuLock {
+            path: path.into(),
+            timeout,
+        });
+        self
+    }
+
+    /// Makes every getter refresh the table first, unless it was refreshed recently, defaults to `false`
+    ///
+    /// Each refresh is an SMU round trip that makes it write out a new table, so the
+    /// table is refreshed at most once per [`RyzenAdjBuilder::auto_refresh_interval`]. Reading many
+    /// values in a row then still returns values of the same refresh.
+    pub fn auto_refresh(mut self, auto_refresh: bool) -> Self {
+        self.auto_refresh = auto_refresh;
+        self
+    }
+
+    /// Sets the minimum time between two refreshes of [`RyzenAdjBuilder::auto_refresh`], defaults to [`AUTO_REFRESH_INTERVAL`]
+    ///
+    /// `Duration::ZERO` refreshes before every single read
+    pub fn auto_refresh_interval(mut self, interval: Duration) -> Self {
+        self.auto_refresh_interval = Some(interval);
+        self
+    }
+
+    /// Overrides the detection of [`RyzenAdj::power_scale`], for firmware the heuristic gets wrong
+    pub fn power_scale(mut self, power_scale: PowerScale) -> Self {
+        self.power_scale = Some(power_scale);
+        self
+    }
+
+    /// Limits the offsets accepted by `set_coall` and `set_coper` to -`max`..=`max`, defaults to 30
+    ///
+    /// This only ever tightens the range, values above 30 keep the default -30..=30
+    pub fn max_curve_offset(mut self, max: u32) -> Self {
+        self.max_curve_offset = Some(max);
+        self
+    }
+
+    /// Skips the family detection, [`RyzenAdj::get_cpu_family`] returns `family` instead
+    ///
+    /// Range validation and [`RyzenAdj::supported_params`] then follow the assumed family, for fixed
+    /// hardware or a newer stepping ryzenadj does not detect yet. **A wrong assumption can allow
+    /// unsafe sets**, ryzenadj itself still checks the family it detected when sending values.
+    pub fn assume_family(mut self, family: RyzenFamily) -> Self {
+        self.assume_family = Some(family);
+        self
+    }
+
+    /// Curve offsets accepted by instances opened with this builder
+    fn curve_offset(&self) -> ParamRange {
+        match self.max_curve_offset {
+            Some(max) => {
+                let max = i64::from(max).min(limits::CURVE_OFFSET.max);
+                ParamRange {
+                    min: -max,
+                    max,
+                    ..limits::CURVE_OFFSET
+                }
+            }
+            None => limits::CURVE_OFFSET,
+        }
+    }
+
+    /// Opens ryzenadj with the configured options
+    pub fn build(self) -> RyzenAdjResult<RyzenAdj> {
+        self.open(|| unsafe { libryzenadj_sys::init_ryzenadj() })
+    }
+
+    /// Takes ownership of the ryzenadj handle returned by `init` and inits its table,
+    /// `init` is only called once the instance may be opened
+    pub(crate) fn open(
+        self,
+        init: impl FnOnce() -> libryzenadj_sys::ryzen_access,
+    ) -> RyzenAdjResult<RyzenAdj> {
+        acquire(self.multi_open)?;
+        let ryzen_adj = init();
+        if ryzen_adj.is_null() {
+            let err = init_error(errno());
+            release();
+            Err(err)
+        } else {
+            let init_table_result = unsafe { libryzenadj_sys::init_table(ryzen_adj) };
+
+            let init_table_result = if init_table_result != 0 {
+                Some(init_table_result)
+            } else {
+                None
+            };
+
+            let mut ryzen_adj = self.instance(ryzen_adj, init_table_result);
+            ryzen_adj.defaults = ryzen_adj.current_profile().ok();
+            ryzen_adj.auto_refresh = self
+                .auto_refresh
+                .then(|| self.auto_refresh_interval.unwrap_or(AUTO_REFRESH_INTERVAL));
+            Ok(ryzen_adj)
+        }
+    }
+
+    /// Opens an instance on a null handle without calling into ryzenadj, its getters fail with
+    /// [`RyzenAdjError::InitTableError`]
+    #[cfg(test)]
+    pub(crate) fn open_detached(self) -> RyzenAdjResult<RyzenAdj> {
+        acquire(self.multi_open)?;
+        Ok(self.instance(
+            std::ptr::null_mut(),
+            Some(libryzenadj_sys::ADJ_ERR_MEMORY_ACCESS),
+        ))
+    }
+
+    /// Wraps an acquired handle, without defaults and with auto refresh still off
+    fn instance(
+        &self,
+        ryzen_adj: libryzenadj_sys::ryzen_access,
+        init_table_result: Option<i32>,
+    ) -> RyzenAdj {
+        RyzenAdj {
+            ryzen_adj,
+            init_table_result,
+            last_refresh: Cell::new(None),
+            oc_enabled: Cell::new(false),
+            oc_volt: Cell::new(None),
+            skin_temp_power_limit: Cell::new(None),
+            min_gfxclk_freq: Cell::new(None),
+            max_gfxclk_freq: Cell::new(None),
+            power_scale: Cell::new(self.power_scale),
+            curve_offset: self.curve_offset(),
+            assumed_family: self.assume_family,
+            pending_refresh: Cell::new(None),
+            nan_policy: Cell::new(self.nan_policy),
+            defaults: None,
+            smu_lock: self.smu_lock.clone(),
+            auto_refresh: None,
+            config: self.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn init_errno_maps_to_variant() {
+        for errno in [EPERM, EACCES] {
+            assert!(matches!(
+                init_error(Errno(errno)),
+                RyzenAdjError::PermissionDenied
+            ));
+        }
+        for errno in [ENOENT, ENODEV] {
+            assert!(matches!(
+                init_error(Errno(errno)),
+                RyzenAdjError::DriverNotFound
+            ));
+        }
+        // EIO
+        assert!(matches!(
+            init_error(Errno(5)),
+            RyzenAdjError::InitError { errno: Errno(5) }
+        ));
+    }
+}
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+
+use crate::{Metric, PowerSnapshot};
+
+/// Keeps the values of the last `capacity` snapshots per metric, for sparklines and similar
+///
+/// Every metric buffer holds each sample twice, at `pos` and `pos + capacity`, so the retained
+/// window is always one contiguous slice and pushing never reallocates.
+#[derive(Debug, Clone)]
+pub struct MetricHistory {
+    capacity: usize,
+    pos: usize,
+    len: usize,
+    values: HashMap<Metric, Vec<Option<f32>>>,
+}
+
+impl MetricHistory {
+    /// Returns an empty history keeping up to `capacity` samples
+    ///
+    /// # Panics
+    /// - if `capacity` is zero
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "MetricHistory capacity must be non-zero");
+        let values = Metric::ALL
+            .iter()
+            .map(|&metric| (metric, vec![None; capacity * 2]))
+            .collect();
+        Self {
+            capacity,
+            pos: 0,
+            len: 0,
+            values,
+        }
+    }
+
+    /// Adds the values of a snapshot, dropping the oldest sample once full
+    pub fn push(&mut self, snapshot: &PowerSnapshot) {
+        for (metric, value) in snapshot.iter() {
+            if let Some(buffer) = self.values.get_mut(&metric) {
+                buffer[self.pos] = value;
+                buffer[self.pos + self.capacity] = value;
+            }
+        }
+        self.pos = (self.pos + 1) % self.capacity;
+        self.len = (self.len + 1).min(self.capacity);
+    }
+
+    /// Returns the retained values of a metric, oldest first
+    pub fn series(&self, metric: Metric) -> &[Option<f32>] {
+        let start = if self.len == self.capacity {
+            self.pos
+        } else {
+            0
+        };
+        &self.values[&metric][start..start + self.len]
+    }
+
+    /// Number of retained samples
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns if no sample was pushed yet
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Maximum number of retained samples
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
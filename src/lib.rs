@@ -1,18 +1,64 @@
-use errno::{errno, Errno};
+use std::cell::Cell;
+use std::fmt;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use errno::Errno;
 use num_enum::TryFromPrimitive;
 use thiserror::Error;
 
 pub use libryzenadj_sys;
 
+mod builder;
+mod history;
+mod limits;
+mod lock;
+mod monitor;
+mod power_mode;
+mod profile;
+#[cfg(all(feature = "server", unix))]
+mod server;
+mod snapshot;
+mod stats;
+mod summary;
+mod units;
+
+pub use builder::{NanPolicy, RyzenAdjBuilder, AUTO_REFRESH_INTERVAL};
+pub use history::MetricHistory;
+pub use limits::{FamilyCapabilities, ParamRange, SetParam, SETTABLE_PARAMS, WRITABLE_PARAMS};
+pub use lock::{SMU_LOCK_DIR, SMU_LOCK_FILE};
+pub use monitor::SelfTestReport;
+pub use power_mode::{PowerMode, PowerModeSettings};
+#[cfg(feature = "serde")]
+pub use profile::PROFILE_VERSION;
+pub use profile::{LimitGuard, LimitWatcher, Profile, ProfileMonitor, DELTA_TOLERANCE};
+pub use snapshot::{
+    format_with_units, CoreMetrics, Metric, PowerSnapshot, FLAT_LEN, FLAT_METRICS, MAX_CORES,
+    READONLY_METRICS,
+};
+pub use stats::{MetricStats, SmoothedStream, StatsAccumulator, StatsReport};
+pub use summary::{
+    CoreTempStats, GpuState, LimitReading, MemorySummary, PptState, PptWindow, TemperatureSource,
+    TemperatureSummary, UncoreSummary, COUPLED_RATIO_TOLERANCE, THROTTLE_RISK_WEIGHTS,
+};
+pub use units::{vid_to_volts, volts_to_vid, Amps, Megahertz, Milliamps, PowerScale, MAX_VID};
+
 ///  Enumerates the possible errors returned from ryzenadj
 #[derive(Error, Debug)]
 pub enum RyzenAdjError {
-    /// ryzenadj struct init failed, provides `errno` returned by the library
-    #[error("ryzenadj init failed: {} {}", errno, errno.0)]
-    InitError { errno: Errno },
+    /// ryzenadj struct init failed for another reason than [`RyzenAdjError::PermissionDenied`] or
+    /// [`RyzenAdjError::DriverNotFound`], provides `errno` returned by the library, which is also its `source`
+    #[error("ryzenadj init failed with errno {}", errno.0)]
+    InitError {
+        #[source]
+        errno: Errno,
+    },
     /// this error is returned when ryzenadj can not read out values from msr, setting values might still work
+    ///
+    /// Returned by all getters if the table init failed on open, and by [`RyzenAdj::refresh`]
     #[error("ryzenadj table init failed: {0}, set functions might still work")]
-    InitTableError(i32),
+    InitTableError(TableError),
     /// reading given value returned a NaN float
     #[error("ryzenadj get returned NaN")]
     GetNaN,
@@ -37,10 +83,176 @@ pub enum RyzenAdjError {
     /// unknow error ocured when tring to set give value
     #[error("ryzenadj adj unknow error {0}")]
     AdjUnknowError(i32),
-    /// given value is out of allowed range
-    #[error("ryzenadj adj value out or range")]
-    AdjValueOutOfRange,
+    /// given value is out of allowed range, bounds are in the unit of the setter (millivolts for volt based setters)
+    #[error("ryzenadj adj value {value} out of range {min}..={max}")]
+    AdjValueOutOfRange { value: i64, min: i64, max: i64 },
+    /// overclocking has to be enabled with `set_enable_oc` before using this setter
+    #[error("ryzenadj overclocking not enabled, call set_enable_oc first")]
+    OcNotEnabled,
+    /// the cpu vendor is not AMD, ryzenadj can not be used on this system
+    #[error("ryzenadj cpu is not an AMD cpu")]
+    NotAmdCpu,
+    /// the core index does not fit into the per core curve optimiser encoding
+    #[error("ryzenadj core index {core} out of range")]
+    CoreIndexOutOfRange { core: u32 },
+    /// the PPT limits are not ordered stapm <= slow <= fast
+    #[error("ryzenadj inconsistent ppt limits: stapm {stapm} <= slow {slow} <= fast {fast} does not hold")]
+    InconsistentPptLimits { stapm: u32, fast: u32, slow: u32 },
+    /// ryzenadj init failed because the process lacks the privileges to access the SMU
+    #[error("ryzenadj init failed: permission denied")]
+    PermissionDenied,
+    /// ryzenadj init failed because neither the ryzen_smu driver nor /dev/mem access is available
+    #[error("ryzenadj init failed: driver not found")]
+    DriverNotFound,
+    /// another instance is already open in this process, see [`RyzenAdjBuilder::multi_open`]
+    #[error("ryzenadj is already open in this process")]
+    AlreadyOpen,
+    /// the SMU lock enabled with [`RyzenAdjBuilder::smu_lock`] is held by another process for longer than its timeout
+    #[error("ryzenadj smu is busy, lock not acquired")]
+    SmuBusy,
+    /// an io operation failed, e.g. serving metrics with the `server` feature, the io error is its `source`
+    ///
+    /// Converts from [`std::io::Error`], so `?` works in functions returning [`RyzenAdjResult`]
+    #[error("ryzenadj io failed")]
+    Io(#[from] std::io::Error),
+    /// applying a [`PowerModeSettings`] failed at `field`, the setter name without its `set_` prefix
+    /// (see [`SetParam::name`]), the error of the setter is its `source`
+    ///
+    /// Settings before `field` were already applied
+    #[error("ryzenadj applying {field} failed")]
+    ApplyFailed {
+        field: &'static str,
+        #[source]
+        source: Box<RyzenAdjError>,
+    },
+    /// a float value passed to a setter is NaN or infinite
+    #[error("ryzenadj value {value} is not a finite number")]
+    ValueNotFinite { value: f32 },
+    /// the background thread of a [`ProfileMonitor`] or [`LimitWatcher`] panicked while opening its instance
+    #[error("ryzenadj background thread panicked")]
+    PollerPanicked,
+    /// the limits could not be read when the instance was opened, so there is nothing to restore, see
+    /// [`RyzenAdj::reset_to_defaults`]
+    #[error("ryzenadj has no limits read on open to restore")]
+    NoDefaults,
+}
+
+impl RyzenAdjError {
+    /// Returns a stable numeric code for the error variant, meant for passing errors over FFI or IPC
+    ///
+    /// Codes are never reused or changed between versions, they are unrelated to the raw SMU return codes
+    pub fn code(&self) -> i32 {
+        match self {
+            RyzenAdjError::InitError { .. } => 1,
+            RyzenAdjError::InitTableError(_) => 2,
+            RyzenAdjError::GetNaN => 3,
+            RyzenAdjError::UnknowFamily(_) => 4,
+            RyzenAdjError::AdjFamilyNotSupported => 5,
+            RyzenAdjError::AdjMemoryAccessError => 6,
+            RyzenAdjError::AdjSmuRejected => 7,
+            RyzenAdjError::AdjSmuTimeout => 8,
+            RyzenAdjError::AdjSmuUnsupported => 9,
+            RyzenAdjError::AdjUnknowError(_) => 10,
+            RyzenAdjError::AdjValueOutOfRange { .. } => 11,
+            RyzenAdjError::OcNotEnabled => 12,
+            RyzenAdjError::NotAmdCpu => 13,
+            RyzenAdjError::CoreIndexOutOfRange { .. } => 14,
+            RyzenAdjError::InconsistentPptLimits { .. } => 15,
+            RyzenAdjError::PermissionDenied => 16,
+            RyzenAdjError::DriverNotFound => 17,
+            RyzenAdjError::AlreadyOpen => 18,
+            RyzenAdjError::SmuBusy => 19,
+            RyzenAdjError::Io(_) => 20,
+            RyzenAdjError::ApplyFailed { .. } => 21,
+            RyzenAdjError::ValueNotFinite { .. } => 22,
+            RyzenAdjError::PollerPanicked => 23,
+            RyzenAdjError::NoDefaults => 24,
+        }
+    }
+
+    /// Returns a short remediation for the error, meant to be shown to users next to the `Display` message
+    pub fn hint(&self) -> &'static str {
+        match self {
+            RyzenAdjError::InitError { .. } => "run as root and make sure the ryzen_smu driver or /dev/mem access is available",
+            RyzenAdjError::InitTableError(TableError::PmTableVersion) => "the firmware does not report its pm table, update the BIOS or libryzenadj, setting values might still work",
+            RyzenAdjError::InitTableError(TableError::MemoryMap) => "run as root and make sure memory access is not blocked by kernel lockdown or secure boot",
+            RyzenAdjError::InitTableError(error) if error.is_transient() => "another tool is reading the pm table at the same time, retry",
+            RyzenAdjError::InitTableError(_) => "reading values is not supported on this system, setting values might still work",
+            RyzenAdjError::GetNaN => "this value is not reported by this cpu family",
+            RyzenAdjError::UnknowFamily(_) => "this cpu family is not known yet, update libryzenadj",
+            RyzenAdjError::AdjFamilyNotSupported => "this setting is not supported on this cpu family",
+            RyzenAdjError::AdjMemoryAccessError => "run as root and make sure memory access is not blocked by kernel lockdown or secure boot",
+            RyzenAdjError::AdjSmuRejected => "the cpu rejected the value, try a less extreme one",
+            RyzenAdjError::AdjSmuTimeout => "the cpu did not respond in time, retry or check if another tool is using the SMU",
+            RyzenAdjError::AdjSmuUnsupported => "this setting is not supported by the cpu firmware",
+            RyzenAdjError::AdjUnknowError(_) => "retry, and report the error code if it persists",
+            RyzenAdjError::AdjValueOutOfRange { .. } => "lower the requested value into the allowed range",
+            RyzenAdjError::OcNotEnabled => "enable overclocking with set_enable_oc first",
+            RyzenAdjError::NotAmdCpu => "ryzenadj only works on AMD Ryzen cpus",
+            RyzenAdjError::CoreIndexOutOfRange { .. } => "use a core index reported by the cpu",
+            RyzenAdjError::InconsistentPptLimits { .. } => {
+                "keep the stapm limit at or below the slow limit, and the slow limit at or below the fast limit"
+            }
+            RyzenAdjError::PermissionDenied => "run as root or grant the CAP_SYS_RAWIO capability",
+            RyzenAdjError::DriverNotFound => {
+                "load the ryzen_smu kernel module, or boot with iomem=relaxed for /dev/mem access"
+            }
+            RyzenAdjError::AlreadyOpen => "reuse the open instance, or open with multi_open",
+            RyzenAdjError::SmuBusy => "another tool is using the SMU, retry later or increase the lock timeout",
+            RyzenAdjError::Io(_) => "check that the path exists and is accessible",
+            RyzenAdjError::ApplyFailed { source, .. } => source.hint(),
+            RyzenAdjError::ValueNotFinite { .. } => "pass a finite number, check the calculation producing it",
+            RyzenAdjError::PollerPanicked => "retry, and report the panic message if it persists",
+            RyzenAdjError::NoDefaults => "reopen ryzenadj once the limits can be read, or apply a profile instead",
+        }
+    }
+}
+
+/// Reason `init_table` or `refresh_table` failed, carried by [`RyzenAdjError::InitTableError`]
+///
+/// PM table versions ryzenadj does not know are not an error, they are read with a guessed table size,
+/// so values of those may be wrong or NaN instead
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableError {
+    /// the SMU did not report a PM table version, or does not support the table version request
+    #[error("pm table version is not available")]
+    PmTableVersion,
+    /// the PM table could not be mapped or copied from physical memory
+    #[error("pm table memory could not be mapped")]
+    MemoryMap,
+    /// ryzenadj does not support reading the PM table of this cpu family
+    #[error("pm table is not supported on this family")]
+    FamilyNotSupported,
+    /// the SMU rejected the table transfer, e.g. because another tool requested one at the same time
+    #[error("pm table transfer was rejected")]
+    SmuRejected,
+    /// the SMU did not respond to the table request in time
+    #[error("pm table request timed out")]
+    SmuTimeout,
+    /// any other raw return code
+    #[error("unknown error {0}")]
+    Unknown(i32),
 }
+
+impl TableError {
+    /// Decodes a non zero return code of `init_table` or `refresh_table`
+    pub fn from_code(code: i32) -> Self {
+        match code {
+            libryzenadj_sys::ADJ_ERR_SMU_UNSUPPORTED => TableError::PmTableVersion,
+            libryzenadj_sys::ADJ_ERR_MEMORY_ACCESS => TableError::MemoryMap,
+            libryzenadj_sys::ADJ_ERR_FAM_UNSUPPORTED => TableError::FamilyNotSupported,
+            libryzenadj_sys::ADJ_ERR_SMU_REJECTED => TableError::SmuRejected,
+            libryzenadj_sys::ADJ_ERR_SMU_TIMEOUT => TableError::SmuTimeout,
+            code => TableError::Unknown(code),
+        }
+    }
+
+    /// Returns if retrying may succeed, which is the case for a rejected or timed out table transfer
+    pub fn is_transient(&self) -> bool {
+        matches!(self, TableError::SmuRejected | TableError::SmuTimeout)
+    }
+}
+
 /// libryzenadj result type returned by all available functions
 pub type RyzenAdjResult<T> = Result<T, RyzenAdjError>;
 
@@ -48,10 +260,26 @@ pub type RyzenAdjResult<T> = Result<T, RyzenAdjError>;
 pub struct RyzenAdj {
     ryzen_adj: libryzenadj_sys::ryzen_access,
     init_table_result: Option<i32>,
+    last_refresh: Cell<Option<Instant>>,
+    oc_enabled: Cell<bool>,
+    oc_volt: Cell<Option<u32>>,
+    skin_temp_power_limit: Cell<Option<u32>>,
+    min_gfxclk_freq: Cell<Option<u32>>,
+    max_gfxclk_freq: Cell<Option<u32>>,
+    power_scale: Cell<Option<PowerScale>>,
+    curve_offset: ParamRange,
+    assumed_family: Option<RyzenFamily>,
+    pending_refresh: Cell<Option<JoinHandle<RyzenAdjResult<()>>>>,
+    nan_policy: Cell<NanPolicy>,
+    defaults: Option<Profile>,
+    smu_lock: Option<lock::SmuLock>,
+    auto_refresh: Option<Duration>,
+    /// options this instance was opened with, reused by the background pollers
+    config: RyzenAdjBuilder,
 }
 
 /// Enumerates supported CPU families
-#[derive(Debug, TryFromPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
 #[non_exhaustive]
 #[repr(i32)]
 pub enum RyzenFamily {
@@ -83,45 +311,367 @@ pub enum RyzenFamily {
     Strixpoint = libryzenadj_sys::ryzen_family_FAM_STRIXPOINT,
 }
 
+/// Error codes matched by `RyzenAdj::adj_code`
+const ADJ_ERRORS: [i32; 5] = [
+    libryzenadj_sys::ADJ_ERR_FAM_UNSUPPORTED,
+    libryzenadj_sys::ADJ_ERR_MEMORY_ACCESS,
+    libryzenadj_sys::ADJ_ERR_SMU_REJECTED,
+    libryzenadj_sys::ADJ_ERR_SMU_TIMEOUT,
+    libryzenadj_sys::ADJ_ERR_SMU_UNSUPPORTED,
+];
+
+// Checks the bindgen constants at compile time, so a header bump that remaps them fails the build
+// instead of silently changing which error a return code maps to. Family discriminants are already
+// distinct as the compiler rejects duplicate enum discriminants.
+const _: () = {
+    let mut i = 0;
+    while i < ADJ_ERRORS.len() {
+        assert!(ADJ_ERRORS[i] < 0, "ADJ_ERR_* codes must be negative");
+        let mut j = i + 1;
+        while j < ADJ_ERRORS.len() {
+            assert!(
+                ADJ_ERRORS[i] != ADJ_ERRORS[j],
+                "ADJ_ERR_* codes must be distinct"
+            );
+            j += 1;
+        }
+        i += 1;
+    }
+    assert!(
+        libryzenadj_sys::ryzen_family_FAM_UNKNOWN < 0,
+        "FAM_UNKNOWN must be negative"
+    );
+    let families = [
+        RyzenFamily::Raven,
+        RyzenFamily::Picassso,
+        RyzenFamily::Renoir,
+        RyzenFamily::Cezanne,
+        RyzenFamily::Dali,
+        RyzenFamily::Lucienne,
+        RyzenFamily::Vangogh,
+        RyzenFamily::Rembrandt,
+        RyzenFamily::Mendocino,
+        RyzenFamily::Phoenix,
+        RyzenFamily::Hawkpoint,
+        RyzenFamily::Strixpoint,
+    ];
+    let mut i = 0;
+    while i < families.len() {
+        let family = families[i] as i32;
+        assert!(
+            family >= 0 && family < libryzenadj_sys::ryzen_family_FAM_END,
+            "known families must be in 0..FAM_END"
+        );
+        i += 1;
+    }
+};
+
+/// SMU BIOS interface version, returned by [`RyzenAdj::bios_interface_version`]
+///
+/// Holds the same number as [`RyzenAdj::get_bios_if_ver`]. The SMU reports a single interface
+/// number that is not packed into major/minor parts, so there is nothing to decode, this type only
+/// keeps it apart from other integers and displays it like the ryzenadj CLI does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BiosIfVersion(pub u32);
+
+impl fmt::Display for BiosIfVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 impl RyzenAdj {
     /// Returns a new RyzenAdj instance
+    ///
+    /// Fails with [`RyzenAdjError::AlreadyOpen`] if another instance is live in this process,
+    /// see [`RyzenAdjBuilder::multi_open`]
     pub fn new() -> RyzenAdjResult<Self> {
-        let ryzen_adj = unsafe { libryzenadj_sys::init_ryzenadj() };
+        Self::builder().build()
+    }
 
-        if ryzen_adj.is_null() {
-            Err(RyzenAdjError::InitError { errno: errno() })
+    /// Returns a new RyzenAdj instance like [`RyzenAdj::new`], panicking if it can not be opened
+    ///
+    /// Meant for quick scripts that can only abort anyway, the panic message includes the error and its
+    /// [`RyzenAdjError::hint`]. Libraries should use [`RyzenAdj::new`] and pass the error on.
+    ///
+    /// # Panics
+    /// - if [`RyzenAdj::new`] fails
+    pub fn new_or_panic() -> Self {
+        match Self::new() {
+            Ok(ryzen_adj) => ryzen_adj,
+            Err(err) => panic!("failed to open ryzenadj: {err}, {}", err.hint()),
+        }
+    }
+
+    /// Re-applies the limits read when this instance was opened and disables overclocking, as a best-effort reset
+    ///
+    /// ryzenadj can not read the BIOS defaults, so limits changed by other tools before this instance was
+    /// opened are kept. A true BIOS default may need a reboot. Fails with the table init error if the
+    /// table could not be initialized, and with [`RyzenAdjError::NoDefaults`] if the limits could not
+    /// be read on open for another reason.
+    pub fn reset_to_defaults(&self) -> RyzenAdjResult<()> {
+        self.is_init_table()?;
+        let defaults = self.defaults.as_ref().ok_or(RyzenAdjError::NoDefaults)?;
+        self.apply_profile(defaults)?;
+        if self.get_cpu_family()?.capabilities().overclock {
+            self.set_disable_oc()?;
+        }
+        Ok(())
+    }
+
+    /// Registers a panic hook restoring the limits read when this instance was opened and disabling overclocking
+    ///
+    /// This is a safety net for tools experimenting with risky values: on a panic the hook does what
+    /// [`RyzenAdj::reset_to_defaults`] does before the panic unwinds, then calls the previously installed
+    /// hook. Panic hooks are global to the process, so the restore also runs for panics on other threads
+    /// and stays installed after this instance is dropped. Installing it again chains another restore.
+    /// The hook opens its own instance with the options this one was opened with, including its SMU
+    /// lock, and [`RyzenAdjBuilder::multi_open`], and ignores all errors.
+    ///
+    /// The limits restored are the ones read on open rather than limits recommended for the family, so
+    /// a panic returns to the state before this tool changed anything. Fails like
+    /// [`RyzenAdj::reset_to_defaults`] if the limits could not be read on open.
+    pub fn install_safe_restore_hook(&self) -> RyzenAdjResult<()> {
+        self.is_init_table()?;
+        let defaults = self.defaults.clone().ok_or(RyzenAdjError::NoDefaults)?;
+        let overclock = self.get_cpu_family()?.capabilities().overclock;
+        let config = self.config.clone().multi_open(true);
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if let Ok(ryzen_adj) = config.clone().build() {
+                let _ = ryzen_adj.apply_profile(&defaults);
+                if overclock {
+                    let _ = ryzen_adj.set_disable_oc();
+                }
+            }
+            previous(info);
+        }));
+        Ok(())
+    }
+
+    /// Returns a builder to open an instance with non default options
+    pub fn builder() -> RyzenAdjBuilder {
+        RyzenAdjBuilder::new()
+    }
+
+    /// Wraps a handle returned by `libryzenadj_sys::init_ryzenadj` and inits its table
+    ///
+    /// Ownership of the handle is transferred, it is cleaned up when the returned instance is dropped.
+    /// A null handle returns the same init errors as [`RyzenAdj::new`]. The instance is opened
+    /// as with [`RyzenAdjBuilder::multi_open`], so it never fails with [`RyzenAdjError::AlreadyOpen`].
+    ///
+    /// # Safety
+    /// - `ryzen_adj` has to be null or a valid handle from `init_ryzenadj` that was not cleaned up yet
+    /// - the handle must not be used or cleaned up elsewhere afterwards
+    pub unsafe fn from_raw(ryzen_adj: libryzenadj_sys::ryzen_access) -> RyzenAdjResult<Self> {
+        RyzenAdjBuilder::new().multi_open(true).open(|| ryzen_adj)
+    }
+
+    /// Returns the underlying handle without cleaning it up, the caller has to call
+    /// `libryzenadj_sys::cleanup_ryzenadj` on it
+    ///
+    /// Everything else of the instance is dropped, and it no longer counts as open for
+    /// [`RyzenAdjBuilder::multi_open`]
+    pub fn into_raw(mut self) -> libryzenadj_sys::ryzen_access {
+        self.finish_pending_refresh();
+        // dropping self with a null handle skips the cleanup
+        std::mem::replace(&mut self.ryzen_adj, std::ptr::null_mut())
+    }
+
+    /// Returns the underlying handle for calling functions of `libryzenadj_sys` not covered here
+    ///
+    /// The instance keeps ownership and still cleans the handle up when dropped, so it must not be
+    /// cleaned up or used after that. Misusing it (e.g. setting values behind the back of this instance)
+    /// can make state tracked here, like [`RyzenAdj::is_oc_enabled`], wrong.
+    pub fn as_raw(&self) -> libryzenadj_sys::ryzen_access {
+        self.ryzen_adj
+    }
+
+    /// Returns a new RyzenAdj instance, after checking through CPUID that this is an AMD cpu
+    ///
+    /// Fails with [`RyzenAdjError::NotAmdCpu`] on other vendors and non x86 systems
+    pub fn new_checked() -> RyzenAdjResult<Self> {
+        if Self::is_amd_cpu() {
+            Self::new()
         } else {
-            let init_table_result = unsafe { libryzenadj_sys::init_table(ryzen_adj) };
+            Err(RyzenAdjError::NotAmdCpu)
+        }
+    }
 
-            let init_table_result = if init_table_result != 0 {
-                Some(init_table_result)
-            } else {
-                None
-            };
+    #[cfg(target_arch = "x86_64")]
+    fn is_amd_cpu() -> bool {
+        // __cpuid is only safe to call on newer compilers
+        #[allow(unused_unsafe)]
+        let cpuid = unsafe { std::arch::x86_64::__cpuid(0) };
+        let mut vendor = [0u8; 12];
+        vendor[0..4].copy_from_slice(&cpuid.ebx.to_le_bytes());
+        vendor[4..8].copy_from_slice(&cpuid.edx.to_le_bytes());
+        vendor[8..12].copy_from_slice(&cpuid.ecx.to_le_bytes());
+        &vendor == b"AuthenticAMD"
+    }
 
-            Ok(Self {
-                ryzen_adj,
-                init_table_result,
-            })
+    #[cfg(not(target_arch = "x86_64"))]
+    fn is_amd_cpu() -> bool {
+        false
+    }
+
+    /// Returns if the process likely has the privileges ryzenadj needs, without opening it
+    ///
+    /// On Linux this checks for an effective uid of 0 or the `CAP_SYS_RAWIO` capability in
+    /// `/proc/self/status`. It is a heuristic only, init can still fail (e.g. when blocked by
+    /// lockdown or secure boot). Other platforms are not checked and always return `true`.
+    pub fn has_required_privileges() -> bool {
+        #[cfg(target_os = "linux")]
+        {
+            const CAP_SYS_RAWIO: u32 = 17;
+
+            let Ok(status) = std::fs::read_to_string("/proc/self/status") else {
+                return false;
+            };
+            let field = |name: &str| {
+                status
+                    .lines()
+                    .find_map(|line| line.strip_prefix(name))
+                    .map(str::trim)
+            };
+            let euid_root = field("Uid:")
+                .and_then(|uids| uids.split_whitespace().nth(1))
+                .is_some_and(|euid| euid == "0");
+            let cap_sys_rawio = field("CapEff:")
+                .and_then(|caps| u64::from_str_radix(caps, 16).ok())
+                .is_some_and(|caps| caps & (1 << CAP_SYS_RAWIO) != 0);
+            euid_root || cap_sys_rawio
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            true
         }
     }
 
     fn is_init_table(&self) -> RyzenAdjResult<()> {
         if let Some(init_table_result) = self.init_table_result {
-            Err(RyzenAdjError::InitTableError(init_table_result))
+            Err(RyzenAdjError::InitTableError(TableError::from_code(
+                init_table_result,
+            )))
         } else {
             Ok(())
         }
     }
 
-    fn is_nan(value: f32) -> RyzenAdjResult<f32> {
-        if value.is_nan() {
+    /// Checks the table before a getter reads it, refreshing it first if auto refresh is enabled
+    fn prepare_read(&self) -> RyzenAdjResult<()> {
+        self.is_init_table()?;
+        self.finish_pending_refresh();
+        if let Some(interval) = self.auto_refresh {
+            self.refresh_throttled(interval)?;
+        }
+        Ok(())
+    }
+
+    fn is_nan(&self, value: f32) -> RyzenAdjResult<f32> {
+        if !value.is_nan() {
+            Ok(value)
+        } else if self.nan_policy.get() == NanPolicy::Zero {
+            Ok(0.0)
+        } else {
             Err(RyzenAdjError::GetNaN)
+        }
+    }
+
+    /// Runs `read` with NaN readings returned as [`RyzenAdjError::GetNaN`], whatever the [`NanPolicy`]
+    ///
+    /// For reads that have to tell values the CPU does not report apart from real zeros
+    pub(crate) fn ignoring_nan_policy<T>(&self, read: impl FnOnce() -> T) -> T {
+        let policy = self.nan_policy.replace(NanPolicy::None);
+        let result = read();
+        self.nan_policy.set(policy);
+        result
+    }
+
+    /// Reads a per core value, cores from [`MAX_CORES`] on are read as NaN without calling into ryzenadj
+    ///
+    /// The table only stores values for the first [`MAX_CORES`] cores, so this never reads past them
+    /// whatever the core count of the CPU is
+    fn core_value(core: u32, read: impl FnOnce() -> f32) -> f32 {
+        if core < MAX_CORES {
+            read()
         } else {
-            Ok(value)
+            f32::NAN
+        }
+    }
+
+    /// Like [`RyzenAdj::is_nan`] for power readings, converting them to W if [`RyzenAdj::power_scale`] detects mW
+    fn is_nan_power(&self, value: f32) -> RyzenAdjResult<f32> {
+        let value = self.is_nan(value)?;
+        match self.detect_power_scale() {
+            Some(PowerScale::Milliwatts) => Ok(value / 1000.0),
+            // W is what ryzenadj documents, so it is assumed when the scale can not be detected
+            Some(PowerScale::Watts) | None => Ok(value),
+        }
+    }
+
+    /// Returns the overridden or previously detected power scale, otherwise detects it
+    ///
+    /// Only a certain scale is cached, see [`units::guess_power_scale`]
+    fn detect_power_scale(&self) -> Option<PowerScale> {
+        if let Some(scale) = self.power_scale.get() {
+            return Some(scale);
+        }
+        let fast_limit = unsafe { libryzenadj_sys::get_fast_limit(self.ryzen_adj) };
+        let socket_power = unsafe { libryzenadj_sys::get_socket_power(self.ryzen_adj) };
+        let (scale, certain) = units::guess_power_scale(fast_limit, socket_power)?;
+        if certain {
+            self.power_scale.set(Some(scale));
+        }
+        Some(scale)
+    }
+
+    fn check_range(value: i64, min: i64, max: i64) -> RyzenAdjResult<()> {
+        if (min..=max).contains(&value) {
+            Ok(())
+        } else {
+            Err(RyzenAdjError::AdjValueOutOfRange { value, min, max })
         }
     }
 
+    /// Fails with [`RyzenAdjError::AdjFamilyNotSupported`] if the family lacks the knob covering `param`
+    ///
+    /// Works without the table, like the setters. Family numbers this crate does not know are passed
+    /// through, ryzenadj decides for those.
+    fn check_supported(&self, param: SetParam) -> RyzenAdjResult<()> {
+        let family = match self.assumed_family {
+            Some(family) => family,
+            None => {
+                self.finish_pending_refresh();
+                let family_int = unsafe { libryzenadj_sys::get_cpu_family(self.ryzen_adj) };
+                match RyzenFamily::try_from(family_int) {
+                    Ok(family) => family,
+                    Err(_) => return Ok(()),
+                }
+            }
+        };
+        if family.capabilities().supports(param) {
+            Ok(())
+        } else {
+            Err(RyzenAdjError::AdjFamilyNotSupported)
+        }
+    }
+
+    /// Takes the SMU lock if enabled, held until the returned guard is dropped
+    fn lock_smu(&self) -> RyzenAdjResult<Option<std::fs::File>> {
+        self.smu_lock
+            .as_ref()
+            .map(lock::SmuLock::acquire)
+            .transpose()
+    }
+
+    /// Runs a setter while holding the SMU lock and maps its return code
+    fn adj_locked(&self, set: impl FnOnce() -> i32) -> RyzenAdjResult<()> {
+        self.finish_pending_refresh();
+        let _lock = self.lock_smu()?;
+        Self::adj_code(set())
+    }
+
     fn adj_code(code: i32) -> RyzenAdjResult<()> {
         match code {
             0 => Ok(()),
@@ -136,251 +686,384 @@ impl RyzenAdj {
     /// Refresh current readed values from the CPU
     pub fn refresh(&self) -> RyzenAdjResult<()> {
         self.is_init_table()?;
-        let result = unsafe { libryzenadj_sys::refresh_table(self.ryzen_adj) };
-        if result != 0 {
-            Err(RyzenAdjError::InitTableError(result))
-        } else {
-            Ok(())
+        self.finish_pending_refresh();
+        refresh_table(self.smu_lock.as_ref(), self.ryzen_adj)?;
+        self.last_refresh.set(Some(Instant::now()));
+        Ok(())
+    }
+    /// Refresh current readed values from the CPU, unless the last refresh happened less than `min_interval` ago
+    ///
+    /// Returns `true` if the table was actually refreshed. The time of the last refresh is kept
+    /// in a `Cell` so this works through `&self`, it is updated by [`RyzenAdj::refresh`] too.
+    pub fn refresh_throttled(&self, min_interval: Duration) -> RyzenAdjResult<bool> {
+        if let Some(last_refresh) = self.last_refresh.get() {
+            if last_refresh.elapsed() < min_interval {
+                return Ok(false);
+            }
+        }
+        self.refresh()?;
+        Ok(true)
+    }
+    /// Gets the shortest interval worth refreshing the table at on this family
+    ///
+    /// The table holds values the SMU averages over time and temperatures, which barely change within
+    /// [`AUTO_REFRESH_INTERVAL`] (100 ms), so polling faster mostly re-reads the same values while
+    /// keeping the SMU busy. AMD documents no per family update cadence, so every known family uses
+    /// that interval. Unknown families, or if the family can not be read, get a conservative 1 s.
+    pub fn recommended_refresh_interval(&self) -> Duration {
+        match self.get_cpu_family() {
+            Ok(RyzenFamily::Unknow) | Err(_) => Duration::from_secs(1),
+            Ok(_) => AUTO_REFRESH_INTERVAL,
+        }
+    }
+    /// Refresh current readed values from the CPU on a worker thread, giving up waiting after `timeout`
+    ///
+    /// Returns `false` if the refresh did not complete in time, which happens when the SMU is busy
+    /// or another process holds the [`RyzenAdjBuilder::smu_lock`] lock. The refresh is then left
+    /// running on the worker thread and may still complete later, which the next call checks for:
+    /// `try_refresh` returns `false` right away while it is still running, every other call blocks
+    /// until it finished, as the table must not be read or the SMU used concurrently. Errors of a
+    /// refresh that timed out are discarded. Dropping the instance waits for it as well.
+    pub fn try_refresh(&self, timeout: Duration) -> RyzenAdjResult<bool> {
+        self.is_init_table()?;
+        if let Some(pending) = self.pending_refresh.take() {
+            if !pending.is_finished() {
+                self.pending_refresh.set(Some(pending));
+                return Ok(false);
+            }
+            let _ = self.join_refresh(pending);
+        }
+
+        let (done, done_rx) = mpsc::channel();
+        let ryzen_adj = SendAccess(self.ryzen_adj);
+        let smu_lock = self.smu_lock.clone();
+        let worker = thread::spawn(move || {
+            let result = refresh_table(smu_lock.as_ref(), ryzen_adj.get());
+            let _ = done.send(());
+            result
+        });
+        match done_rx.recv_timeout(timeout) {
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => {
+                self.join_refresh(worker)?;
+                Ok(true)
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                self.pending_refresh.set(Some(worker));
+                Ok(false)
+            }
+        }
+    }
+    /// Waits for the worker thread of a refresh started by [`RyzenAdj::try_refresh`]
+    fn join_refresh(&self, worker: JoinHandle<RyzenAdjResult<()>>) -> RyzenAdjResult<()> {
+        match worker.join() {
+            Ok(result) => {
+                result?;
+                self.last_refresh.set(Some(Instant::now()));
+                Ok(())
+            }
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    }
+    /// Waits for a refresh of [`RyzenAdj::try_refresh`] that timed out, discarding its errors
+    fn finish_pending_refresh(&self) {
+        if let Some(pending) = self.pending_refresh.take() {
+            let _ = self.join_refresh(pending);
+        }
+    }
+    /// Gets the scale of the power values in the table
+    ///
+    /// Depending on family and firmware, ryzenadj may report power in mW instead of W. The scale
+    /// is detected from the fast PPT limit, or the socket power if the limit is not reported: values
+    /// above 1000 can only be mW. It is detected once per instance on the first power reading, except
+    /// that a socket power of 1000 or below is assumed W and detected again on the next reading, as it
+    /// may be a low mW reading. It can be overridden with [`RyzenAdjBuilder::power_scale`]. All power getters use it to always
+    /// return W. Returns [`RyzenAdjError::GetNaN`] if neither value is reported, the getters then
+    /// assume W.
+    pub fn power_scale(&self) -> RyzenAdjResult<PowerScale> {
+        self.prepare_read()?;
+        self.detect_power_scale().ok_or(RyzenAdjError::GetNaN)
+    }
+    /// Copies the raw PM table read by the last refresh, for reverse engineering unknown values
+    ///
+    /// # Safety
+    /// - the table is copied from memory owned by ryzenadj, trusting the size it reports, no
+    ///   refresh may run concurrently on this instance
+    /// - the layout is family and firmware dependent and may change with any BIOS update, it is not
+    ///   a stable interface
+    pub unsafe fn raw_table_bytes(&self) -> RyzenAdjResult<Vec<u8>> {
+        self.is_init_table()?;
+        self.finish_pending_refresh();
+        let size = libryzenadj_sys::get_table_size(self.ryzen_adj);
+        let values = libryzenadj_sys::get_table_values(self.ryzen_adj);
+        if values.is_null() {
+            // the table was never mapped, which is what init_table reports an error for as well
+            return Err(RyzenAdjError::InitTableError(TableError::MemoryMap));
         }
+        Ok(std::slice::from_raw_parts(values as *const u8, size).to_vec())
     }
     /// Gets the APU skin temperature limit
     pub fn get_apu_skin_temp_limit(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_apu_skin_temp_limit(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan(unsafe { libryzenadj_sys::get_apu_skin_temp_limit(self.ryzen_adj) })
     }
     /// Gets the APU skin temperature value
     pub fn get_apu_skin_temp_value(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_apu_skin_temp_value(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan(unsafe { libryzenadj_sys::get_apu_skin_temp_value(self.ryzen_adj) })
     }
     /// Gets the APU slow limit
     pub fn get_apu_slow_limit(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_apu_slow_limit(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan_power(unsafe { libryzenadj_sys::get_apu_slow_limit(self.ryzen_adj) })
     }
     /// Gets the APU slow value
     pub fn get_apu_slow_value(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_apu_slow_value(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan_power(unsafe { libryzenadj_sys::get_apu_slow_value(self.ryzen_adj) })
     }
-    /// Gets bios ver
+    /// Gets the SMU BIOS interface version as the raw number reported by the SMU
+    ///
+    /// See [`RyzenAdj::bios_interface_version`] for the typed version
     pub fn get_bios_if_ver(&self) -> RyzenAdjResult<i32> {
         self.is_init_table()?;
+        self.finish_pending_refresh();
         Ok(unsafe { libryzenadj_sys::get_bios_if_ver(self.ryzen_adj) })
     }
+    /// Gets the SMU BIOS interface version, the number of [`RyzenAdj::get_bios_if_ver`] as a [`BiosIfVersion`]
+    pub fn bios_interface_version(&self) -> RyzenAdjResult<BiosIfVersion> {
+        Ok(BiosIfVersion(self.get_bios_if_ver()? as u32))
+    }
     /// Gets cclk busy value
     pub fn get_cclk_busy_value(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_cclk_busy_value(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan(unsafe { libryzenadj_sys::get_cclk_busy_value(self.ryzen_adj) })
     }
     /// Gets cclk setpoint
     pub fn get_cclk_setpoint(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_cclk_setpoint(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan(unsafe { libryzenadj_sys::get_cclk_setpoint(self.ryzen_adj) })
     }
     /// Gets current core clk
     pub fn get_core_clk(&self, core: u32) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_core_clk(self.ryzen_adj, core) })
+        self.prepare_read()?;
+        self.is_nan(Self::core_value(core, || unsafe {
+            libryzenadj_sys::get_core_clk(self.ryzen_adj, core)
+        }))
     }
     /// Gets current core power
     pub fn get_core_power(&self, core: u32) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_core_power(self.ryzen_adj, core) })
+        self.prepare_read()?;
+        self.is_nan_power(Self::core_value(core, || unsafe {
+            libryzenadj_sys::get_core_power(self.ryzen_adj, core)
+        }))
     }
     /// Gets current core temp
     pub fn get_core_temp(&self, core: u32) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_core_temp(self.ryzen_adj, core) })
+        self.prepare_read()?;
+        self.is_nan(Self::core_value(core, || unsafe {
+            libryzenadj_sys::get_core_temp(self.ryzen_adj, core)
+        }))
     }
     /// Gets current core volt
     pub fn get_core_volt(&self, core: u32) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_core_volt(self.ryzen_adj, core) })
+        self.prepare_read()?;
+        self.is_nan(Self::core_value(core, || unsafe {
+            libryzenadj_sys::get_core_volt(self.ryzen_adj, core)
+        }))
     }
-    /// Gets the cpu family
+    /// Gets the cpu family, or the one set with [`RyzenAdjBuilder::assume_family`]
     pub fn get_cpu_family(&self) -> RyzenAdjResult<RyzenFamily> {
         self.is_init_table()?;
+        if let Some(family) = self.assumed_family {
+            return Ok(family);
+        }
+        self.finish_pending_refresh();
         let family_int = unsafe { libryzenadj_sys::get_cpu_family(self.ryzen_adj) };
         RyzenFamily::try_from(family_int).map_err(|_| RyzenAdjError::UnknowFamily(family_int))
     }
     /// Gets the dgpu skin temp limit
     pub fn get_dgpu_skin_temp_limit(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_dgpu_skin_temp_limit(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan(unsafe { libryzenadj_sys::get_dgpu_skin_temp_limit(self.ryzen_adj) })
     }
     /// Gets the dgpu skin temp value
     pub fn get_dgpu_skin_temp_value(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_dgpu_skin_temp_value(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan(unsafe { libryzenadj_sys::get_dgpu_skin_temp_value(self.ryzen_adj) })
     }
     /// Gets the fast limit
     pub fn get_fast_limit(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_fast_limit(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan_power(unsafe { libryzenadj_sys::get_fast_limit(self.ryzen_adj) })
     }
     /// Gets the fast value
     pub fn get_fast_value(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_fast_value(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan_power(unsafe { libryzenadj_sys::get_fast_value(self.ryzen_adj) })
     }
     /// Gets fclk
     pub fn get_fclk(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_fclk(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan(unsafe { libryzenadj_sys::get_fclk(self.ryzen_adj) })
     }
     /// Gets gfx tmp
     pub fn get_gfx_temp(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_gfx_temp(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan(unsafe { libryzenadj_sys::get_gfx_temp(self.ryzen_adj) })
     }
     /// Gets gfx clk
     pub fn get_gfx_clk(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_gfx_clk(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan(unsafe { libryzenadj_sys::get_gfx_clk(self.ryzen_adj) })
     }
     /// Gets gfx volt
     pub fn get_gfx_volt(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_gfx_volt(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan(unsafe { libryzenadj_sys::get_gfx_volt(self.ryzen_adj) })
     }
     /// Gets l3 cache clk
     pub fn get_l3_clk(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_l3_clk(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan(unsafe { libryzenadj_sys::get_l3_clk(self.ryzen_adj) })
     }
     /// Gets l3 logic
     pub fn get_l3_logic(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_l3_logic(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan_power(unsafe { libryzenadj_sys::get_l3_logic(self.ryzen_adj) })
     }
     /// Gets l3 temp
     pub fn get_l3_temp(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_l3_temp(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan(unsafe { libryzenadj_sys::get_l3_temp(self.ryzen_adj) })
     }
     /// Gets l3 vddm
     pub fn get_l3_vddm(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_l3_vddm(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan_power(unsafe { libryzenadj_sys::get_l3_vddm(self.ryzen_adj) })
     }
     /// Gets mem clk
     pub fn get_mem_clk(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_mem_clk(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan(unsafe { libryzenadj_sys::get_mem_clk(self.ryzen_adj) })
     }
     /// Gets psi0 current
     pub fn get_psi0_current(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_psi0_current(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan(unsafe { libryzenadj_sys::get_psi0_current(self.ryzen_adj) })
     }
     /// Gets psi0soc current
     pub fn get_psi0soc_current(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_psi0soc_current(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan(unsafe { libryzenadj_sys::get_psi0soc_current(self.ryzen_adj) })
     }
     /// Gets slow limit
     pub fn get_slow_limit(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_slow_limit(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan_power(unsafe { libryzenadj_sys::get_slow_limit(self.ryzen_adj) })
     }
     /// Gets slow time
     pub fn get_slow_time(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_slow_time(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan(unsafe { libryzenadj_sys::get_slow_time(self.ryzen_adj) })
     }
     /// Gets slow value
     pub fn get_slow_value(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_slow_value(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan_power(unsafe { libryzenadj_sys::get_slow_value(self.ryzen_adj) })
     }
     /// Gets soc power
     pub fn get_soc_power(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_soc_power(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan_power(unsafe { libryzenadj_sys::get_soc_power(self.ryzen_adj) })
     }
     /// Gets soc voltage
     pub fn get_soc_volt(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_soc_volt(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan(unsafe { libryzenadj_sys::get_soc_volt(self.ryzen_adj) })
     }
     /// Gets socket power
     pub fn get_socket_power(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_socket_power(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan_power(unsafe { libryzenadj_sys::get_socket_power(self.ryzen_adj) })
     }
     /// Gets stamp limit
     pub fn get_stapm_limit(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_stapm_limit(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan_power(unsafe { libryzenadj_sys::get_stapm_limit(self.ryzen_adj) })
     }
     /// Gets stamp time
     pub fn get_stapm_time(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_stapm_time(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan(unsafe { libryzenadj_sys::get_stapm_time(self.ryzen_adj) })
     }
     /// Gets stamp value
     pub fn get_stapm_value(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_stapm_value(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan_power(unsafe { libryzenadj_sys::get_stapm_value(self.ryzen_adj) })
     }
     /// Gets tctl temp
     pub fn get_tctl_temp(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_tctl_temp(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan(unsafe { libryzenadj_sys::get_tctl_temp(self.ryzen_adj) })
     }
     /// Gets tctl temp value
     pub fn get_tctl_temp_value(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_tctl_temp_value(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan(unsafe { libryzenadj_sys::get_tctl_temp_value(self.ryzen_adj) })
     }
     /// Gets vrm current
     pub fn get_vrm_current(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_vrm_current(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan(unsafe { libryzenadj_sys::get_vrm_current(self.ryzen_adj) })
     }
     /// Gets vrm current value
     pub fn get_vrm_current_value(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_vrm_current_value(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan(unsafe { libryzenadj_sys::get_vrm_current_value(self.ryzen_adj) })
     }
     /// Gets vrmmax current
     pub fn get_vrmmax_current(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_vrmmax_current(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan(unsafe { libryzenadj_sys::get_vrmmax_current(self.ryzen_adj) })
     }
     /// Gets vrmmax current value
     pub fn get_vrmmax_current_value(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_vrmmax_current_value(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan(unsafe { libryzenadj_sys::get_vrmmax_current_value(self.ryzen_adj) })
     }
     /// Gets vrmsoc current
     pub fn get_vrmsoc_current(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_vrmsoc_current(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan(unsafe { libryzenadj_sys::get_vrmsoc_current(self.ryzen_adj) })
     }
     /// Gets vrmsoc current value
     pub fn get_vrmsoc_current_value(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_vrmsoc_current_value(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan(unsafe { libryzenadj_sys::get_vrmsoc_current_value(self.ryzen_adj) })
     }
     /// Gets vrmsocmax current
     pub fn get_vrmsocmax_current(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_vrmsocmax_current(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan(unsafe { libryzenadj_sys::get_vrmsocmax_current(self.ryzen_adj) })
     }
     /// Gets vrmsocmax current value
     pub fn get_vrmsocmax_current_value(&self) -> RyzenAdjResult<f32> {
-        self.is_init_table()?;
-        Self::is_nan(unsafe { libryzenadj_sys::get_vrmsocmax_current_value(self.ryzen_adj) })
+        self.prepare_read()?;
+        self.is_nan(unsafe { libryzenadj_sys::get_vrmsocmax_current_value(self.ryzen_adj) })
     }
     /// Sets the apu skin temp limit
     pub fn set_apu_skin_temp_limit(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_apu_skin_temp_limit(self.ryzen_adj, value) })
+        self.check_supported(SetParam::ApuSkinTempLimit)?;
+        limits::check_ceiling(SetParam::ApuSkinTempLimit, value)?;
+        self.adj_locked(|| unsafe {
+            libryzenadj_sys::set_apu_skin_temp_limit(self.ryzen_adj, value)
+        })
     }
     /// Sets the apu slow limit
     pub fn set_apu_slow_limit(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_apu_slow_limit(self.ryzen_adj, value) })
+        self.check_supported(SetParam::ApuSlowLimit)?;
+        limits::check_ceiling(SetParam::ApuSlowLimit, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_apu_slow_limit(self.ryzen_adj, value) })
     }
     /// Sets the all core curve optimiser
     ///
@@ -389,8 +1072,9 @@ impl RyzenAdj {
     ///
     /// # Safety
     /// - `value` needs to be in proper range, the base of this value is 0x100000 and can go -/+ 30 decimal
+    #[cfg(feature = "raw-curve")]
     pub unsafe fn set_unsafe_coall(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(libryzenadj_sys::set_coall(self.ryzen_adj, value))
+        self.adj_locked(|| libryzenadj_sys::set_coall(self.ryzen_adj, value))
     }
     /// Sets the igpu curve optimiser
     ///
@@ -399,8 +1083,9 @@ impl RyzenAdj {
     ///
     /// # Safety
     /// - `value` needs to be in proper range, the base of this value is 0x100000 and can go -/+ 30 decimal
+    #[cfg(feature = "raw-curve")]
     pub unsafe fn set_unsafe_cogfx(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(libryzenadj_sys::set_cogfx(self.ryzen_adj, value))
+        self.adj_locked(|| libryzenadj_sys::set_cogfx(self.ryzen_adj, value))
     }
     /// Sets the per core curve optimiser
     ///
@@ -411,190 +1096,944 @@ impl RyzenAdj {
     /// - `value` needs to be in proper range, the base of this value is 0x100000
     /// the formula for per core Curve Optimizer (on a single CCD mobile APU) is <core number> * 0x100000 + ((0x100000 + <value>) & 0xFFFFF).
     /// for example to set -10 on core no.2 and -5 on core no.3 it's:
+    #[cfg(feature = "raw-curve")]
     pub unsafe fn set_unsafe_coper(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(libryzenadj_sys::set_coper(self.ryzen_adj, value))
+        self.adj_locked(|| libryzenadj_sys::set_coper(self.ryzen_adj, value))
     }
 
     /// Sets the all core curve optimiser
+    ///
+    /// Offsets outside of -30..=30, or the tighter range set with [`RyzenAdjBuilder::max_curve_offset`],
+    /// return [`RyzenAdjError::AdjValueOutOfRange`]
     pub fn set_coall(&self, value: i32) -> RyzenAdjResult<()> {
-        if (-30..=30).contains(&value) {
-            let value = 0x100000 + value;
-            Self::adj_code(unsafe { libryzenadj_sys::set_coall(self.ryzen_adj, value as u32) })
-        } else {
-            Err(RyzenAdjError::AdjValueOutOfRange)
-        }
+        self.check_supported(SetParam::Coall)?;
+        self.curve_offset.check(value.into())?;
+        let value = 0x100000 + value;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_coall(self.ryzen_adj, value as u32) })
     }
 
     /// Sets the per core curve optimiser
+    ///
+    /// Encoded as `core * 0x100000 + ((0x100000 + value) & 0xFFFFF)`, cores that do not fit into
+    /// the upper 12 bits return [`RyzenAdjError::CoreIndexOutOfRange`]. Offsets are checked like
+    /// [`RyzenAdj::set_coall`] does.
     pub fn set_coper(&self, core: u32, value: i32) -> RyzenAdjResult<()> {
-        if (-30..=30).contains(&value) {
-            let value = (core * 0x100000) as i32 + value;
-            Self::adj_code(unsafe { libryzenadj_sys::set_coper(self.ryzen_adj, value as u32) })
-        } else {
-            Err(RyzenAdjError::AdjValueOutOfRange)
+        self.check_supported(SetParam::Coper)?;
+        self.curve_offset.check(value.into())?;
+        let value = encode_coper(core, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_coper(self.ryzen_adj, value) })
+    }
+
+    /// Sets the per core curve optimiser of cores `0..offsets.len()`, `offsets[core]` for each core
+    ///
+    /// All offsets are checked like [`RyzenAdj::set_coper`] does before any is written, so an out of range
+    /// offset writes nothing. Cores are then written in order, stopping at the first error, cores before
+    /// it stay applied. ryzenadj has no readback for curve offsets on any family, so verification that
+    /// the offsets stuck is skipped until it does and a successful return only means the SMU accepted them.
+    pub fn set_coper_all_verified(&self, offsets: &[i32]) -> RyzenAdjResult<()> {
+        for &offset in offsets {
+            self.curve_offset.check(offset.into())?;
+        }
+        for (core, &offset) in (0u32..).zip(offsets) {
+            self.set_coper(core, offset)?;
         }
+        Ok(())
     }
 
     /// Sets the dgpu skin temp limit
     pub fn set_dgpu_skin_temp_limit(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_coall(self.ryzen_adj, value) })
+        self.check_supported(SetParam::DgpuSkinTempLimit)?;
+        limits::check_ceiling(SetParam::DgpuSkinTempLimit, value)?;
+        self.adj_locked(|| unsafe {
+            libryzenadj_sys::set_dgpu_skin_temp_limit(self.ryzen_adj, value)
+        })
     }
     /// Enable overclock (Renoir and up Only)
     pub fn set_enable_oc(&self) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_enable_oc(self.ryzen_adj) })
+        // covered by the overclock knob, like oc_clk
+        self.check_supported(SetParam::OcClk)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_enable_oc(self.ryzen_adj) })?;
+        self.oc_enabled.set(true);
+        Ok(())
     }
     /// Disable overclock (Renoir and up Only)
     pub fn set_disable_oc(&self) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_disable_oc(self.ryzen_adj) })
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_disable_oc(self.ryzen_adj) })?;
+        self.oc_enabled.set(false);
+        Ok(())
+    }
+    /// Returns if overclocking was enabled through this instance
+    ///
+    /// ryzenadj can not read back the overclock state, so this only tracks calls to
+    /// `set_enable_oc`/`set_disable_oc`, overclocking enabled by other tools is not detected
+    pub fn is_oc_enabled(&self) -> bool {
+        self.oc_enabled.get()
+    }
+
+    fn check_oc_enabled(&self) -> RyzenAdjResult<()> {
+        if self.is_oc_enabled() {
+            Ok(())
+        } else {
+            Err(RyzenAdjError::OcNotEnabled)
+        }
     }
     /// Sets the fast limit
     pub fn set_fast_limit(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_fast_limit(self.ryzen_adj, value) })
+        limits::check_ceiling(SetParam::FastLimit, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_fast_limit(self.ryzen_adj, value) })
     }
     /// Sets the gfx clk
     pub fn set_gfx_clk(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_gfx_clk(self.ryzen_adj, value) })
+        self.check_supported(SetParam::GfxClk)?;
+        limits::check_ceiling(SetParam::GfxClk, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_gfx_clk(self.ryzen_adj, value) })
+    }
+    /// Sets the gfx clk, rejecting values above [`Megahertz::MAX`]
+    pub fn set_gfx_clk_mhz(&self, value: Megahertz) -> RyzenAdjResult<()> {
+        self.set_gfx_clk(value.checked()?)
+    }
+    /// Sets the gfx clk, rejecting values outside of the min/max gfxclk frequency window
+    ///
+    /// The SMU rejects a gfx clk below the min or above the max gfxclk frequency, so set those first.
+    /// ryzenadj can not read the window back, this checks against the last values set through
+    /// `set_min_gfxclk_freq`/`set_max_gfxclk_freq` on this instance, a bound that was not set is
+    /// only limited to 0..=[`Megahertz::MAX`].
+    pub fn set_gfx_clk_checked(&self, value: u32) -> RyzenAdjResult<()> {
+        let min = self.min_gfxclk_freq.get().unwrap_or(0);
+        let max = self.max_gfxclk_freq.get().unwrap_or(Megahertz::MAX.0);
+        Self::check_range(value.into(), min.into(), max.into())?;
+        self.set_gfx_clk(value)
     }
     /// Sets maximum Transmission (CPU-GPU) Frequency
     pub fn set_max_fclk_freq(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_max_fclk_freq(self.ryzen_adj, value) })
+        self.check_supported(SetParam::MaxFclkFreq)?;
+        limits::check_ceiling(SetParam::MaxFclkFreq, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_max_fclk_freq(self.ryzen_adj, value) })
+    }
+    /// Sets maximum Transmission (CPU-GPU) Frequency, rejecting values above [`Megahertz::MAX`]
+    pub fn set_max_fclk_freq_mhz(&self, value: Megahertz) -> RyzenAdjResult<()> {
+        self.set_max_fclk_freq(value.checked()?)
     }
     /// Sets max gfxclk frequency
     pub fn set_max_gfxclk_freq(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_max_gfxclk_freq(self.ryzen_adj, value) })
+        self.check_supported(SetParam::MaxGfxclkFreq)?;
+        limits::check_ceiling(SetParam::MaxGfxclkFreq, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_max_gfxclk_freq(self.ryzen_adj, value) })?;
+        self.max_gfxclk_freq.set(Some(value));
+        Ok(())
+    }
+    /// Sets max gfxclk frequency, rejecting values above [`Megahertz::MAX`]
+    pub fn set_max_gfxclk_freq_mhz(&self, value: Megahertz) -> RyzenAdjResult<()> {
+        self.set_max_gfxclk_freq(value.checked()?)
     }
     /// Sets the max lclk
     pub fn set_max_lclk(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_max_lclk(self.ryzen_adj, value) })
+        self.check_supported(SetParam::MaxLclk)?;
+        limits::check_ceiling(SetParam::MaxLclk, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_max_lclk(self.ryzen_adj, value) })
+    }
+    /// Sets the max lclk, rejecting values above [`Megahertz::MAX`]
+    pub fn set_max_lclk_mhz(&self, value: Megahertz) -> RyzenAdjResult<()> {
+        self.set_max_lclk(value.checked()?)
     }
     /// Sets max perfomence mode
     pub fn set_max_performance(&self) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_max_performance(self.ryzen_adj) })
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_max_performance(self.ryzen_adj) })
     }
     /// Sets max socclk freq
     pub fn set_max_socclk_freq(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_max_socclk_freq(self.ryzen_adj, value) })
+        self.check_supported(SetParam::MaxSocclkFreq)?;
+        limits::check_ceiling(SetParam::MaxSocclkFreq, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_max_socclk_freq(self.ryzen_adj, value) })
+    }
+    /// Sets max socclk freq, rejecting values above [`Megahertz::MAX`]
+    pub fn set_max_socclk_freq_mhz(&self, value: Megahertz) -> RyzenAdjResult<()> {
+        self.set_max_socclk_freq(value.checked()?)
     }
     /// Sets maximum Video Core Next freq
     pub fn set_max_vcn(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_max_vcn(self.ryzen_adj, value) })
+        self.check_supported(SetParam::MaxVcn)?;
+        limits::check_ceiling(SetParam::MaxVcn, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_max_vcn(self.ryzen_adj, value) })
+    }
+    /// Sets maximum Video Core Next freq, rejecting values above [`Megahertz::MAX`]
+    pub fn set_max_vcn_mhz(&self, value: Megahertz) -> RyzenAdjResult<()> {
+        self.set_max_vcn(value.checked()?)
     }
     /// Sets minimum Transmission (CPU-GPU) Frequency (MHz)
     pub fn set_min_fclk_freq(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_min_fclk_freq(self.ryzen_adj, value) })
+        self.check_supported(SetParam::MinFclkFreq)?;
+        limits::check_ceiling(SetParam::MinFclkFreq, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_min_fclk_freq(self.ryzen_adj, value) })
+    }
+    /// Sets minimum Transmission (CPU-GPU) Frequency, rejecting values above [`Megahertz::MAX`]
+    pub fn set_min_fclk_freq_mhz(&self, value: Megahertz) -> RyzenAdjResult<()> {
+        self.set_min_fclk_freq(value.checked()?)
     }
     /// Sets min gfxclk frequency
     pub fn set_min_gfxclk_freq(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_min_gfxclk_freq(self.ryzen_adj, value) })
+        self.check_supported(SetParam::MinGfxclkFreq)?;
+        limits::check_ceiling(SetParam::MinGfxclkFreq, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_min_gfxclk_freq(self.ryzen_adj, value) })?;
+        self.min_gfxclk_freq.set(Some(value));
+        Ok(())
+    }
+    /// Sets min gfxclk frequency, rejecting values above [`Megahertz::MAX`]
+    pub fn set_min_gfxclk_freq_mhz(&self, value: Megahertz) -> RyzenAdjResult<()> {
+        self.set_min_gfxclk_freq(value.checked()?)
     }
     /// Sets min lclk
     pub fn set_min_lclk(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_min_lclk(self.ryzen_adj, value) })
+        self.check_supported(SetParam::MinLclk)?;
+        limits::check_ceiling(SetParam::MinLclk, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_min_lclk(self.ryzen_adj, value) })
+    }
+    /// Sets min lclk, rejecting values above [`Megahertz::MAX`]
+    pub fn set_min_lclk_mhz(&self, value: Megahertz) -> RyzenAdjResult<()> {
+        self.set_min_lclk(value.checked()?)
     }
     /// Sets min socclk freq
     pub fn set_min_socclk_freq(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_min_socclk_freq(self.ryzen_adj, value) })
+        self.check_supported(SetParam::MinSocclkFreq)?;
+        limits::check_ceiling(SetParam::MinSocclkFreq, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_min_socclk_freq(self.ryzen_adj, value) })
+    }
+    /// Sets min socclk freq, rejecting values above [`Megahertz::MAX`]
+    pub fn set_min_socclk_freq_mhz(&self, value: Megahertz) -> RyzenAdjResult<()> {
+        self.set_min_socclk_freq(value.checked()?)
     }
     /// Sets min Video Core Next freq
     pub fn set_min_vcn(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_min_vcn(self.ryzen_adj, value) })
+        self.check_supported(SetParam::MinVcn)?;
+        limits::check_ceiling(SetParam::MinVcn, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_min_vcn(self.ryzen_adj, value) })
+    }
+    /// Sets min Video Core Next freq, rejecting values above [`Megahertz::MAX`]
+    pub fn set_min_vcn_mhz(&self, value: Megahertz) -> RyzenAdjResult<()> {
+        self.set_min_vcn(value.checked()?)
     }
-    /// Sets forced Core Clock Speed in MHz (Renoir and up Only)
+    /// Sets forced Core Clock Speed in MHz (Renoir and up Only), requires overclocking to be enabled
     pub fn set_oc_clk(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_oc_clk(self.ryzen_adj, value) })
+        self.check_oc_enabled()?;
+        self.set_oc_clk_unchecked(value)
+    }
+    /// Sets forced Core Clock Speed in MHz (Renoir and up Only), without checking [`RyzenAdj::is_oc_enabled`]
+    pub fn set_oc_clk_unchecked(&self, value: u32) -> RyzenAdjResult<()> {
+        self.check_supported(SetParam::OcClk)?;
+        limits::check_ceiling(SetParam::OcClk, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_oc_clk(self.ryzen_adj, value) })
     }
-    /// Sets forced Core VID: Must follow this calcuation (1.55 - [VID you want to set e.g. 1.25 for 1.25v]) / 0.00625 (Renoir and up Only)
+    /// Sets forced Core Clock Speed (Renoir and up Only), rejecting values above [`Megahertz::MAX`]
+    pub fn set_oc_clk_mhz(&self, value: Megahertz) -> RyzenAdjResult<()> {
+        self.set_oc_clk(value.checked()?)
+    }
+    /// Sets forced Core VID: Must follow this calcuation (1.55 - [VID you want to set e.g. 1.25 for 1.25v]) / 0.00625 (Renoir and up Only), requires overclocking to be enabled
     pub fn set_oc_volt(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_oc_volt(self.ryzen_adj, value) })
+        self.check_oc_enabled()?;
+        self.set_oc_volt_unchecked(value)
+    }
+    /// Sets forced Core VID (Renoir and up Only), without checking [`RyzenAdj::is_oc_enabled`]
+    pub fn set_oc_volt_unchecked(&self, value: u32) -> RyzenAdjResult<()> {
+        self.check_supported(SetParam::OcVolt)?;
+        limits::check_ceiling(SetParam::OcVolt, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_oc_volt(self.ryzen_adj, value) })?;
+        self.oc_volt.set(Some(value));
+        Ok(())
+    }
+    /// Sets forced Core voltage in volts (Renoir and up Only), converted to a VID with [`volts_to_vid`]
+    ///
+    /// Fails with [`RyzenAdjError::ValueNotFinite`] for NaN and infinite voltages
+    pub fn set_oc_volt_v(&self, volts: f32) -> RyzenAdjResult<()> {
+        self.set_oc_volt(units::checked_vid(volts)?)
     }
-    /// Sets forced per Core Clock Speed in MHz (Renoir and up Only)
+    /// Gets the forced Core voltage in volts, converted from the VID with [`vid_to_volts`]
+    ///
+    /// ryzenadj can not read back the forced VID, so this returns the last VID set through this instance
+    /// and [`RyzenAdjError::GetNaN`] if none was set (or it is above [`MAX_VID`])
+    pub fn get_oc_volt_v(&self) -> RyzenAdjResult<f32> {
+        self.oc_volt
+            .get()
+            .and_then(vid_to_volts)
+            .ok_or(RyzenAdjError::GetNaN)
+    }
+    /// Sets forced per Core Clock Speed in MHz (Renoir and up Only), requires overclocking to be enabled
     pub fn set_per_core_oc_clk(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_per_core_oc_clk(self.ryzen_adj, value) })
+        self.check_oc_enabled()?;
+        self.set_per_core_oc_clk_unchecked(value)
+    }
+    /// Sets forced per Core Clock Speed in MHz (Renoir and up Only), without checking [`RyzenAdj::is_oc_enabled`]
+    pub fn set_per_core_oc_clk_unchecked(&self, value: u32) -> RyzenAdjResult<()> {
+        self.check_supported(SetParam::PerCoreOcClk)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_per_core_oc_clk(self.ryzen_adj, value) })
     }
     /// Sets power saving mode
     pub fn set_power_saving(&self) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_power_saving(self.ryzen_adj) })
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_power_saving(self.ryzen_adj) })
+    }
+    /// Sets the STAPM, fast and slow PPT limits (mW) in one call, in that order
+    ///
+    /// The limits are not checked against each other, use [`RyzenAdj::set_ppt_limits_checked`] for that
+    pub fn set_ppt_limits(&self, stapm: u32, fast: u32, slow: u32) -> RyzenAdjResult<()> {
+        self.set_stapm_limit(stapm)?;
+        self.set_fast_limit(fast)?;
+        self.set_slow_limit(slow)
+    }
+    /// Sets the STAPM, fast and slow PPT limits (mW), after checking that stapm <= slow <= fast
+    ///
+    /// Returns [`RyzenAdjError::InconsistentPptLimits`] without writing anything if the order does not hold
+    pub fn set_ppt_limits_checked(&self, stapm: u32, fast: u32, slow: u32) -> RyzenAdjResult<()> {
+        if stapm <= slow && slow <= fast {
+            self.set_ppt_limits(stapm, fast, slow)
+        } else {
+            Err(RyzenAdjError::InconsistentPptLimits { stapm, fast, slow })
+        }
+    }
+    /// Sets the STAPM, fast and slow PPT limits to `percent` of the power budget, clamped to 1..=100
+    ///
+    /// Each limit is set to `min + (max - min) * percent / 100`, where `min` is the lower end of the
+    /// limit's [`SetParam::plausible_range`] and `max` is the limit read when this instance was
+    /// opened (see [`RyzenAdj::reset_to_defaults`]), or the upper end of the range if it could not be
+    /// read. So 100% restores the limits of the device instead of the plausibility bound, and 50% halves
+    /// them. Fails with [`RyzenAdjError::AdjFamilyNotSupported`] if the family has no known range.
+    pub fn set_power_budget_percent(&self, percent: u8) -> RyzenAdjResult<()> {
+        let (stapm, fast, slow) = self.power_budget(percent)?;
+        self.set_ppt_limits(stapm, fast, slow)
+    }
+    /// Refreshes the table and changes the STAPM limit by `delta_mw`, returning the new limit (mW)
+    ///
+    /// The new limit is clamped to the limit's [`SetParam::plausible_range`], so repeated
+    /// increments stop at the upper end instead of failing. Fails with [`RyzenAdjError::GetNaN`] if the
+    /// current limit is not reported and with [`RyzenAdjError::AdjFamilyNotSupported`] if the family
+    /// has no known range.
+    pub fn adjust_stapm_limit(&self, delta_mw: i32) -> RyzenAdjResult<u32> {
+        self.adjust_limit(
+            SetParam::StapmLimit,
+            RyzenAdj::get_stapm_limit,
+            RyzenAdj::set_stapm_limit,
+            delta_mw,
+        )
+    }
+    /// Refreshes the table and changes the fast PPT limit by `delta_mw`, see [`RyzenAdj::adjust_stapm_limit`]
+    pub fn adjust_fast_limit(&self, delta_mw: i32) -> RyzenAdjResult<u32> {
+        self.adjust_limit(
+            SetParam::FastLimit,
+            RyzenAdj::get_fast_limit,
+            RyzenAdj::set_fast_limit,
+            delta_mw,
+        )
+    }
+    /// Refreshes the table and changes the slow PPT limit by `delta_mw`, see [`RyzenAdj::adjust_stapm_limit`]
+    pub fn adjust_slow_limit(&self, delta_mw: i32) -> RyzenAdjResult<u32> {
+        self.adjust_limit(
+            SetParam::SlowLimit,
+            RyzenAdj::get_slow_limit,
+            RyzenAdj::set_slow_limit,
+            delta_mw,
+        )
+    }
+    fn adjust_limit(
+        &self,
+        param: SetParam,
+        get: fn(&RyzenAdj) -> RyzenAdjResult<f32>,
+        set: fn(&RyzenAdj, u32) -> RyzenAdjResult<()>,
+        delta_mw: i32,
+    ) -> RyzenAdjResult<u32> {
+        let range = param
+            .plausible_range(&self.get_cpu_family()?)
+            .ok_or(RyzenAdjError::AdjFamilyNotSupported)?;
+        self.refresh()?;
+        let current = (get(self)? * 1000.0).round() as i64;
+        // i64 holds any u32 limit plus any i32 delta, so this can not over- or underflow
+        let value = (current + i64::from(delta_mw)).clamp(range.min, range.max) as u32;
+        set(self, value)?;
+        Ok(value)
+    }
+    /// Returns the STAPM, fast and slow PPT limits set by [`RyzenAdj::set_power_budget_percent`]
+    pub(crate) fn power_budget(&self, percent: u8) -> RyzenAdjResult<(u32, u32, u32)> {
+        let percent = u64::from(percent.clamp(1, 100));
+        let family = self.get_cpu_family()?;
+        let scale =
+            |param: SetParam, default: fn(&Profile) -> Option<u32>| -> RyzenAdjResult<u32> {
+                let range = param
+                    .plausible_range(&family)
+                    .ok_or(RyzenAdjError::AdjFamilyNotSupported)?;
+                let min = range.min as u64;
+                let max = self
+                    .defaults
+                    .as_ref()
+                    .and_then(default)
+                    .map_or(range.max as u64, u64::from)
+                    .max(min);
+                Ok((min + (max - min) * percent / 100) as u32)
+            };
+        Ok((
+            scale(SetParam::StapmLimit, |defaults| defaults.stapm_limit)?,
+            scale(SetParam::FastLimit, |defaults| defaults.fast_limit)?,
+            scale(SetParam::SlowLimit, |defaults| defaults.slow_limit)?,
+        ))
     }
     /// Sets Ramp Time After Prochot is Deasserted: limit power based on value, higher values does apply tighter limits after prochot is over
+    ///
+    /// ryzenadj does not expose the PROCHOT status itself, so whether it is currently asserted can not be read
     pub fn set_prochot_deassertion_ramp(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe {
+        self.adj_locked(|| unsafe {
             libryzenadj_sys::set_prochot_deassertion_ramp(self.ryzen_adj, value)
         })
     }
     /// Sets PSI0 VDD Current Limit (mA)
     pub fn set_psi0_current(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_psi0_current(self.ryzen_adj, value) })
+        self.check_supported(SetParam::Psi0Current)?;
+        limits::check_ceiling(SetParam::Psi0Current, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_psi0_current(self.ryzen_adj, value) })
     }
     /// Sets PSI0 SoC Current Limit (mA)
     pub fn set_psi0soc_current(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_psi0soc_current(self.ryzen_adj, value) })
+        self.check_supported(SetParam::Psi0socCurrent)?;
+        limits::check_ceiling(SetParam::Psi0socCurrent, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_psi0soc_current(self.ryzen_adj, value) })
     }
     /// Sets PSI3 CPU Current Limit (mA)
     pub fn set_psi3cpu_current(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_psi3cpu_current(self.ryzen_adj, value) })
+        self.check_supported(SetParam::Psi3cpuCurrent)?;
+        limits::check_ceiling(SetParam::Psi3cpuCurrent, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_psi3cpu_current(self.ryzen_adj, value) })
     }
     /// Sets PSI3 GFX Current Limit (mA)
     pub fn set_psi3gfx_current(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_psi3gfx_current(self.ryzen_adj, value) })
+        self.check_supported(SetParam::Psi3gfxCurrent)?;
+        limits::check_ceiling(SetParam::Psi3gfxCurrent, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_psi3gfx_current(self.ryzen_adj, value) })
     }
     /// Sets Skin Temperature Power Limit (mW)
+    ///
+    /// Only honored by the families with [`FamilyCapabilities::skin_temp_power_limit`] set, other
+    /// families fail with [`RyzenAdjError::AdjFamilyNotSupported`]. Read it back with
+    /// [`RyzenAdj::skin_temp_power`].
     pub fn set_skin_temp_power_limit(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_skin_temp_power_limit(self.ryzen_adj, value) })
+        self.check_supported(SetParam::SkinTempPowerLimit)?;
+        limits::check_ceiling(SetParam::SkinTempPowerLimit, value)?;
+        self.adj_locked(|| unsafe {
+            libryzenadj_sys::set_skin_temp_power_limit(self.ryzen_adj, value)
+        })?;
+        self.skin_temp_power_limit.set(Some(value));
+        Ok(())
     }
     /// Sets Average Power Limit - PPT LIMIT SLOW (mW)
     pub fn set_slow_limit(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_slow_limit(self.ryzen_adj, value) })
+        limits::check_ceiling(SetParam::SlowLimit, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_slow_limit(self.ryzen_adj, value) })
     }
     /// Sets Slow PPT Constant Time (s)
     pub fn set_slow_time(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_slow_time(self.ryzen_adj, value) })
+        limits::check_ceiling(SetParam::SlowTime, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_slow_time(self.ryzen_adj, value) })
+    }
+    /// Sets Slow PPT Constant Time (s), rejecting values outside of 1..=600 s
+    ///
+    /// 0 would disable the averaging and longer constants make the slow limit lag too far behind
+    pub fn set_slow_time_checked(&self, value: u32) -> RyzenAdjResult<()> {
+        limits::TIME_S.check(value.into())?;
+        self.set_slow_time(value)
     }
     /// Sets Sustained Power Limit - STAPM LIMIT (mW)
     pub fn set_stapm_limit(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_stapm_limit(self.ryzen_adj, value) })
+        limits::check_ceiling(SetParam::StapmLimit, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_stapm_limit(self.ryzen_adj, value) })
     }
     /// Sets STAPM constant time (s)
     pub fn set_stapm_time(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_stapm_time(self.ryzen_adj, value) })
+        limits::check_ceiling(SetParam::StapmTime, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_stapm_time(self.ryzen_adj, value) })
+    }
+    /// Sets STAPM constant time (s), rejecting values outside of 1..=600 s
+    ///
+    /// 0 would disable the averaging and longer constants make the STAPM limit lag too far behind
+    pub fn set_stapm_time_checked(&self, value: u32) -> RyzenAdjResult<()> {
+        limits::TIME_S.check(value.into())?;
+        self.set_stapm_time(value)
     }
     /// Sets Tctl Temperature Limit (degree C)
     pub fn set_tctl_temp(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_tctl_temp(self.ryzen_adj, value) })
+        limits::check_ceiling(SetParam::TctlTemp, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_tctl_temp(self.ryzen_adj, value) })
     }
     /// Sets VRM Current Limit - TDC LIMIT VDD (mA)
     pub fn set_vrm_current(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_vrm_current(self.ryzen_adj, value) })
+        limits::check_ceiling(SetParam::VrmCurrent, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_vrm_current(self.ryzen_adj, value) })
+    }
+    /// Sets VRM Current Limit - TDC LIMIT VDD in A, rejecting values above [`Milliamps::MAX`]
+    pub fn set_vrm_current_amps(&self, value: Amps) -> RyzenAdjResult<()> {
+        self.set_vrm_current(value.checked()?)
     }
     /// Sets VRM CVIP Current Limit - TDC LIMIT CVIP (mA)
     pub fn set_vrmcvip_current(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_vrmcvip_current(self.ryzen_adj, value) })
+        self.check_supported(SetParam::VrmcvipCurrent)?;
+        limits::check_ceiling(SetParam::VrmcvipCurrent, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_vrmcvip_current(self.ryzen_adj, value) })
+    }
+    /// Sets VRM CVIP Current Limit - TDC LIMIT CVIP in A, rejecting values above [`Milliamps::MAX`]
+    pub fn set_vrmcvip_current_amps(&self, value: Amps) -> RyzenAdjResult<()> {
+        self.set_vrmcvip_current(value.checked()?)
     }
     /// Sets VRM GFX Current Limit - TDC LIMIT GFX (mA)
     pub fn set_vrmgfx_current(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_vrmgfx_current(self.ryzen_adj, value) })
+        self.check_supported(SetParam::VrmgfxCurrent)?;
+        limits::check_ceiling(SetParam::VrmgfxCurrent, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_vrmgfx_current(self.ryzen_adj, value) })
+    }
+    /// Sets VRM GFX Current Limit - TDC LIMIT GFX in A, rejecting values above [`Milliamps::MAX`]
+    pub fn set_vrmgfx_current_amps(&self, value: Amps) -> RyzenAdjResult<()> {
+        self.set_vrmgfx_current(value.checked()?)
     }
     /// Sets VRM GFX Maximum Current Limit - EDC LIMIT GFX (mA)
     pub fn set_vrmgfxmax_current(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_vrmgfxmax_current(self.ryzen_adj, value) })
+        self.check_supported(SetParam::VrmgfxmaxCurrent)?;
+        limits::check_ceiling(SetParam::VrmgfxmaxCurrent, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_vrmgfxmax_current(self.ryzen_adj, value) })
+    }
+    /// Sets VRM GFX Maximum Current Limit - EDC LIMIT GFX in A, rejecting values above [`Milliamps::MAX`]
+    pub fn set_vrmgfxmax_current_amps(&self, value: Amps) -> RyzenAdjResult<()> {
+        self.set_vrmgfxmax_current(value.checked()?)
     }
     /// Sets VRM Maximum Current Limit - EDC LIMIT VDD (mA)
     pub fn set_vrmmax_current(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_vrmmax_current(self.ryzen_adj, value) })
+        limits::check_ceiling(SetParam::VrmmaxCurrent, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_vrmmax_current(self.ryzen_adj, value) })
+    }
+    /// Sets VRM Maximum Current Limit - EDC LIMIT VDD in A, rejecting values above [`Milliamps::MAX`]
+    pub fn set_vrmmax_current_amps(&self, value: Amps) -> RyzenAdjResult<()> {
+        self.set_vrmmax_current(value.checked()?)
     }
     /// Sets VRM SoC Current Limit - TDC LIMIT SoC (mA)
     pub fn set_vrmsoc_current(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_vrmsoc_current(self.ryzen_adj, value) })
+        limits::check_ceiling(SetParam::VrmsocCurrent, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_vrmsoc_current(self.ryzen_adj, value) })
+    }
+    /// Sets VRM SoC Current Limit - TDC LIMIT SoC in A, rejecting values above [`Milliamps::MAX`]
+    pub fn set_vrmsoc_current_amps(&self, value: Amps) -> RyzenAdjResult<()> {
+        self.set_vrmsoc_current(value.checked()?)
     }
     /// Sets VRM SoC Maximum Current Limit - EDC LIMIT SoC (mA)
     pub fn set_vrmsocmax_current(&self, value: u32) -> RyzenAdjResult<()> {
-        Self::adj_code(unsafe { libryzenadj_sys::set_vrmsocmax_current(self.ryzen_adj, value) })
+        self.check_supported(SetParam::VrmsocmaxCurrent)?;
+        limits::check_ceiling(SetParam::VrmsocmaxCurrent, value)?;
+        self.adj_locked(|| unsafe { libryzenadj_sys::set_vrmsocmax_current(self.ryzen_adj, value) })
+    }
+    /// Sets VRM SoC Maximum Current Limit - EDC LIMIT SoC in A, rejecting values above [`Milliamps::MAX`]
+    pub fn set_vrmsocmax_current_amps(&self, value: Amps) -> RyzenAdjResult<()> {
+        self.set_vrmsocmax_current(value.checked()?)
+    }
+}
+
+/// Encodes a per core curve optimiser offset as taken by `set_coper`, see [`RyzenAdj::set_coper`]
+fn encode_coper(core: u32, value: i32) -> RyzenAdjResult<u32> {
+    let core_bits = core
+        .checked_mul(0x100000)
+        .ok_or(RyzenAdjError::CoreIndexOutOfRange { core })?;
+    Ok(core_bits | (0x100000u32.wrapping_add_signed(value) & 0xFFFFF))
+}
+
+/// Refreshes the table while holding the SMU lock, if any
+fn refresh_table(
+    smu_lock: Option<&lock::SmuLock>,
+    ryzen_adj: libryzenadj_sys::ryzen_access,
+) -> RyzenAdjResult<()> {
+    let _lock = smu_lock.map(lock::SmuLock::acquire).transpose()?;
+    let result = unsafe { libryzenadj_sys::refresh_table(ryzen_adj) };
+    if result != 0 {
+        Err(RyzenAdjError::InitTableError(TableError::from_code(result)))
+    } else {
+        Ok(())
+    }
+}
+
+/// Handle moved to the worker thread of [`RyzenAdj::try_refresh`]
+///
+/// The instance joins the worker before it touches the handle again or cleans it up
+struct SendAccess(libryzenadj_sys::ryzen_access);
+
+unsafe impl Send for SendAccess {}
+
+impl SendAccess {
+    // a method, so the closure captures the whole wrapper instead of the raw pointer field
+    fn get(&self) -> libryzenadj_sys::ryzen_access {
+        self.0
     }
 }
 
 impl Drop for RyzenAdj {
     fn drop(&mut self) {
-        unsafe {
-            libryzenadj_sys::cleanup_ryzenadj(self.ryzen_adj);
+        self.finish_pending_refresh();
+        // null once the handle was given away by into_raw
+        if !self.ryzen_adj.is_null() {
+            unsafe {
+                libryzenadj_sys::cleanup_ryzenadj(self.ryzen_adj);
+            }
+        }
+        builder::release();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One error of every variant, in code order
+    fn every_error() -> Vec<RyzenAdjError> {
+        vec![
+            RyzenAdjError::InitError { errno: Errno(5) },
+            RyzenAdjError::InitTableError(TableError::SmuTimeout),
+            RyzenAdjError::GetNaN,
+            RyzenAdjError::UnknowFamily(99),
+            RyzenAdjError::AdjFamilyNotSupported,
+            RyzenAdjError::AdjMemoryAccessError,
+            RyzenAdjError::AdjSmuRejected,
+            RyzenAdjError::AdjSmuTimeout,
+            RyzenAdjError::AdjSmuUnsupported,
+            RyzenAdjError::AdjUnknowError(-9),
+            RyzenAdjError::AdjValueOutOfRange {
+                value: 31,
+                min: -30,
+                max: 30,
+            },
+            RyzenAdjError::OcNotEnabled,
+            RyzenAdjError::NotAmdCpu,
+            RyzenAdjError::CoreIndexOutOfRange { core: 4096 },
+            RyzenAdjError::InconsistentPptLimits {
+                stapm: 30,
+                fast: 10,
+                slow: 20,
+            },
+            RyzenAdjError::PermissionDenied,
+            RyzenAdjError::DriverNotFound,
+            RyzenAdjError::AlreadyOpen,
+            RyzenAdjError::SmuBusy,
+            RyzenAdjError::Io(std::io::ErrorKind::NotFound.into()),
+            RyzenAdjError::ApplyFailed {
+                field: "stapm_limit",
+                source: Box::new(RyzenAdjError::AdjSmuRejected),
+            },
+            RyzenAdjError::ValueNotFinite { value: f32::NAN },
+            RyzenAdjError::PollerPanicked,
+            RyzenAdjError::NoDefaults,
+        ]
+    }
+
+    #[test]
+    fn error_codes_are_distinct_and_stable() {
+        let codes: Vec<i32> = every_error().iter().map(RyzenAdjError::code).collect();
+        // new variants are appended with the next code, existing codes never change
+        let expected: Vec<i32> = (1..=codes.len() as i32).collect();
+        assert_eq!(codes, expected);
+    }
+
+    /// Display of the error and all of its sources, like anyhow prints them with `{:#}`
+    fn chain(error: &dyn std::error::Error) -> Vec<String> {
+        std::iter::successors(Some(error), |error| error.source())
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn init_error_source_is_errno() {
+        let error = RyzenAdjError::InitError { errno: Errno(5) };
+        let chain = chain(&error);
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain[0], "ryzenadj init failed with errno 5");
+        assert_eq!(chain[1], Errno(5).to_string());
+    }
+
+    #[test]
+    fn io_error_converts_and_is_the_source() {
+        fn open() -> RyzenAdjResult<()> {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "no such socket",
+            ))?;
+            Ok(())
+        }
+        let error = open().unwrap_err();
+        assert!(matches!(error, RyzenAdjError::Io(_)));
+        assert_eq!(chain(&error), ["ryzenadj io failed", "no such socket"]);
+    }
+
+    #[test]
+    fn apply_failed_chains_the_setter_error() {
+        let error = RyzenAdjError::ApplyFailed {
+            field: "fast_limit",
+            source: Box::new(RyzenAdjError::InitError { errno: Errno(5) }),
+        };
+        let chain = chain(&error);
+        assert_eq!(chain.len(), 3);
+        assert_eq!(chain[0], "ryzenadj applying fast_limit failed");
+        assert_eq!(chain[1], "ryzenadj init failed with errno 5");
+    }
+
+    #[test]
+    fn errors_without_cause_have_no_source() {
+        use std::error::Error as _;
+
+        assert!(RyzenAdjError::GetNaN.source().is_none());
+        let table = RyzenAdjError::InitTableError(TableError::SmuTimeout);
+        assert!(table.source().is_none());
+        assert!(table
+            .to_string()
+            .contains(&TableError::SmuTimeout.to_string()));
+    }
+
+    #[test]
+    fn adj_error_codes_map_to_distinct_errors() {
+        assert!(RyzenAdj::adj_code(0).is_ok());
+        let codes: Vec<i32> = ADJ_ERRORS
+            .iter()
+            .map(|&code| RyzenAdj::adj_code(code).unwrap_err().code())
+            .collect();
+        for (i, code) in codes.iter().enumerate() {
+            assert_ne!(*code, RyzenAdjError::AdjUnknowError(0).code());
+            assert!(!codes[i + 1..].contains(code), "{codes:?}");
+        }
+        assert!(matches!(
+            RyzenAdj::adj_code(-99),
+            Err(RyzenAdjError::AdjUnknowError(-99))
+        ));
+    }
+
+    #[test]
+    fn table_error_codes_map_to_distinct_errors() {
+        let errors: Vec<TableError> = ADJ_ERRORS
+            .iter()
+            .map(|&code| TableError::from_code(code))
+            .collect();
+        for (i, error) in errors.iter().enumerate() {
+            assert!(!matches!(error, TableError::Unknown(_)), "{error:?}");
+            assert!(!errors[i + 1..].contains(error), "{errors:?}");
+        }
+        assert_eq!(TableError::from_code(-99), TableError::Unknown(-99));
+    }
+
+    #[test]
+    fn family_constants_round_trip() {
+        let families = [
+            (
+                libryzenadj_sys::ryzen_family_FAM_UNKNOWN,
+                RyzenFamily::Unknow,
+            ),
+            (libryzenadj_sys::ryzen_family_FAM_RAVEN, RyzenFamily::Raven),
+            (
+                libryzenadj_sys::ryzen_family_FAM_PICASSO,
+                RyzenFamily::Picassso,
+            ),
+            (
+                libryzenadj_sys::ryzen_family_FAM_RENOIR,
+                RyzenFamily::Renoir,
+            ),
+            (
+                libryzenadj_sys::ryzen_family_FAM_CEZANNE,
+                RyzenFamily::Cezanne,
+            ),
+            (libryzenadj_sys::ryzen_family_FAM_DALI, RyzenFamily::Dali),
+            (
+                libryzenadj_sys::ryzen_family_FAM_LUCIENNE,
+                RyzenFamily::Lucienne,
+            ),
+            (
+                libryzenadj_sys::ryzen_family_FAM_VANGOGH,
+                RyzenFamily::Vangogh,
+            ),
+            (
+                libryzenadj_sys::ryzen_family_FAM_REMBRANDT,
+                RyzenFamily::Rembrandt,
+            ),
+            (
+                libryzenadj_sys::ryzen_family_FAM_MENDOCINO,
+                RyzenFamily::Mendocino,
+            ),
+            (
+                libryzenadj_sys::ryzen_family_FAM_PHOENIX,
+                RyzenFamily::Phoenix,
+            ),
+            (
+                libryzenadj_sys::ryzen_family_FAM_HAWKPOINT,
+                RyzenFamily::Hawkpoint,
+            ),
+            (
+                libryzenadj_sys::ryzen_family_FAM_STRIXPOINT,
+                RyzenFamily::Strixpoint,
+            ),
+        ];
+        for (i, &(constant, family)) in families.iter().enumerate() {
+            assert_eq!(RyzenFamily::try_from(constant).unwrap(), family);
+            assert!(families[i + 1..]
+                .iter()
+                .all(|&(other, _)| other != constant));
+        }
+        assert!(RyzenFamily::try_from(libryzenadj_sys::ryzen_family_FAM_END).is_err());
+    }
+
+    #[test]
+    fn bios_if_version_displays_the_raw_number() {
+        assert_eq!(BiosIfVersion(24).to_string(), "24");
+        assert!(BiosIfVersion(23) < BiosIfVersion(24));
+    }
+
+    #[test]
+    fn coper_encoding() {
+        assert_eq!(encode_coper(0, 0).unwrap(), 0);
+        assert_eq!(encode_coper(0, -10).unwrap(), 0xFFFF6);
+        assert_eq!(encode_coper(1, 5).unwrap(), 0x100005);
+        assert_eq!(encode_coper(3, -30).unwrap(), 0x3FFFE2);
+    }
+
+    #[test]
+    fn coper_rejects_cores_near_overflow() {
+        // the core index takes the upper 12 bits
+        assert_eq!(encode_coper(4095, 30).unwrap(), 0xFFF0001E);
+        assert_eq!(encode_coper(4095, -30).unwrap(), 0xFFFFFFE2);
+        for core in [4096, 4097, u32::MAX / 0x100000 + 1, u32::MAX] {
+            assert!(matches!(
+                encode_coper(core, 0),
+                Err(RyzenAdjError::CoreIndexOutOfRange { core: c }) if c == core
+            ));
+        }
+    }
+
+    /// Returns `(wrapper, sys function)` for every `libryzenadj_sys` call inside a method of this file
+    fn sys_calls() -> Vec<(&'static str, &'static str)> {
+        let mut calls = Vec::new();
+        let mut current = None;
+        for line in include_str!("lib.rs").lines() {
+            if !line.starts_with(' ') && !line.is_empty() {
+                current = None;
+            }
+            let trimmed = line.trim_start();
+            for prefix in ["pub fn ", "pub unsafe fn ", "pub(crate) fn ", "fn "] {
+                if let Some(rest) = trimmed.strip_prefix(prefix) {
+                    current = rest.split(['(', '<']).next();
+                }
+            }
+            let Some(wrapper) = current else { continue };
+            for rest in line.split("libryzenadj_sys::").skip(1) {
+                if let Some((name, _)) = rest.split_once('(') {
+                    if name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                        calls.push((wrapper, name));
+                    }
+                }
+            }
+        }
+        calls
+    }
+
+    #[test]
+    fn getters_and_setters_call_their_sys_fn() {
+        let calls: Vec<_> = sys_calls()
+            .into_iter()
+            .filter(|(wrapper, _)| wrapper.starts_with("get_") || wrapper.starts_with("set_"))
+            .collect();
+        assert!(calls.len() > 80, "only found {} wrapped calls", calls.len());
+        for (wrapper, sys) in calls {
+            let expected = wrapper.replacen("set_unsafe_", "set_", 1);
+            let expected = expected.trim_end_matches("_unchecked");
+            assert_eq!(sys, expected, "{wrapper} calls libryzenadj_sys::{sys}");
+        }
+    }
+
+    #[test]
+    fn into_raw_releases_the_instance_and_its_lock() {
+        let _instances = builder::lock_instances();
+        let path = std::env::temp_dir().join(format!("ryzenadj-raw-{}.lock", std::process::id()));
+        let ryzen_adj = RyzenAdjBuilder::new()
+            .smu_lock_path(&path, Duration::ZERO)
+            .open_detached()
+            .expect("no other instance is open");
+        let held = ryzen_adj.lock_smu().expect("free lock");
+        assert!(held.is_some());
+        drop(held);
+        assert!(matches!(
+            RyzenAdjBuilder::new().open_detached(),
+            Err(RyzenAdjError::AlreadyOpen)
+        ));
+
+        assert!(ryzen_adj.into_raw().is_null());
+        let lock = lock::SmuLock {
+            path: path.clone(),
+            timeout: Duration::ZERO,
+        };
+        assert!(lock.acquire().is_ok(), "the lock is still held");
+        // the handle no longer counts as open
+        drop(
+            RyzenAdjBuilder::new()
+                .open_detached()
+                .expect("instance was released"),
+        );
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn ignoring_nan_policy_reports_nan_and_restores_the_policy() {
+        let _instances = builder::lock_instances();
+        let ryzen_adj = RyzenAdjBuilder::new()
+            .nan_policy(NanPolicy::Zero)
+            .open_detached()
+            .expect("no other instance is open");
+        assert_eq!(ryzen_adj.is_nan(f32::NAN).unwrap(), 0.0);
+        assert!(matches!(
+            ryzen_adj.ignoring_nan_policy(|| ryzen_adj.is_nan(f32::NAN)),
+            Err(RyzenAdjError::GetNaN)
+        ));
+        assert_eq!(ryzen_adj.is_nan(f32::NAN).unwrap(), 0.0);
+    }
+
+    /// Sys getters and setters with no wrapper on purpose
+    const UNWRAPPED_SYS_FNS: &[&str] = &[
+        // the table version only matters for reading the raw table, which `get_table_values` exposes
+        "get_table_ver",
+    ];
+
+    /// Wrapped sys fns neither a [`Metric`] nor a [`SetParam`] generates
+    const OTHER_WRAPPED_SYS_FNS: &[&str] = &[
+        "get_bios_if_ver",
+        "get_core_clk",
+        "get_core_power",
+        "get_core_temp",
+        "get_core_volt",
+        "get_cpu_family",
+        "get_table_size",
+        "get_table_values",
+        "set_cogfx",
+        "set_disable_oc",
+        "set_enable_oc",
+        "set_max_performance",
+        "set_power_saving",
+    ];
+
+    #[test]
+    fn every_sys_getter_and_setter_is_wrapped() {
+        let mut wrapped: Vec<String> = Metric::GETTERS
+            .iter()
+            .map(|getter| getter.to_string())
+            .chain(SETTABLE_PARAMS.iter().map(|name| format!("set_{name}")))
+            .chain(OTHER_WRAPPED_SYS_FNS.iter().map(|name| name.to_string()))
+            .collect();
+        wrapped.sort_unstable();
+        let called: Vec<&str> = sys_calls().into_iter().map(|(_, sys)| sys).collect();
+        for name in &wrapped {
+            assert!(called.contains(&name.as_str()), "{name} is never called");
+        }
+
+        let mut bound: Vec<&str> = libryzenadj_sys::FUNCTIONS
+            .iter()
+            .copied()
+            .filter(|name| name.starts_with("get_") || name.starts_with("set_"))
+            .filter(|name| !UNWRAPPED_SYS_FNS.contains(name))
+            .collect();
+        bound.sort_unstable();
+        assert_eq!(
+            wrapped, bound,
+            "wrap new sys fns or list them in UNWRAPPED_SYS_FNS"
+        );
+        for name in UNWRAPPED_SYS_FNS {
+            assert!(!called.contains(name), "{name} is wrapped");
         }
     }
 }
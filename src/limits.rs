@@ -0,0 +1,550 @@
+use crate::{Megahertz, Milliamps, RyzenAdj, RyzenAdjResult, RyzenFamily, MAX_VID};
+
+/// Plausible values of a [`SetParam`], in the unit taken by its setter, see [`SetParam::plausible_range`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ParamRange {
+    /// lowest accepted value
+    pub min: i64,
+    /// highest accepted value
+    pub max: i64,
+    /// distance between two distinct values
+    pub step: i64,
+}
+
+impl ParamRange {
+    /// Checks that `value` is within the range, failing with [`RyzenAdjError::AdjValueOutOfRange`](crate::RyzenAdjError::AdjValueOutOfRange)
+    pub fn check(&self, value: i64) -> RyzenAdjResult<()> {
+        RyzenAdj::check_range(value, self.min, self.max)
+    }
+}
+
+/// Curve optimiser offsets accepted by `set_coall` and `set_coper`
+pub(crate) const CURVE_OFFSET: ParamRange = ParamRange {
+    min: -30,
+    max: 30,
+    step: 1,
+};
+
+/// Clocks in MHz, up to [`Megahertz::MAX`]
+pub(crate) const CLOCK_MHZ: ParamRange = ParamRange {
+    min: 0,
+    max: Megahertz::MAX.0 as i64,
+    step: 1,
+};
+
+/// VIDs accepted by `set_oc_volt`, up to [`MAX_VID`]
+const VID: ParamRange = ParamRange {
+    min: 0,
+    max: MAX_VID as i64,
+    step: 1,
+};
+
+/// Power limits in mW, up to 150W which no supported APU reaches
+const POWER_MW: ParamRange = ParamRange {
+    min: 0,
+    max: 150_000,
+    step: 1,
+};
+
+/// Current limits in mA, up to [`Milliamps::MAX`] (250A) which no supported APU reaches
+pub(crate) const CURRENT_MA: ParamRange = ParamRange {
+    min: 0,
+    max: Milliamps::MAX.0 as i64,
+    step: 1,
+};
+
+/// Temperature limits in degree C, up to the 105 degree C Tjmax of the supported APUs
+const TEMP_C: ParamRange = ParamRange {
+    min: 0,
+    max: 105,
+    step: 1,
+};
+
+/// PPT time constants in s
+///
+/// Firmware defaults are a few seconds for slow PPT and a few minutes for STAPM. 0 would disable the
+/// averaging, while constants above 10 minutes let the averages lag so far behind that the limits stop
+/// protecting against sustained heat.
+pub(crate) const TIME_S: ParamRange = ParamRange {
+    min: 1,
+    max: 600,
+    step: 1,
+};
+
+macro_rules! set_params {
+    ($($variant:ident => $name:ident;)*) => {
+        /// Every value taking `set_*` function of [`RyzenAdj`], named after the setter without its `set_` prefix
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[non_exhaustive]
+        pub enum SetParam {
+            $(
+                #[doc = concat!("see `RyzenAdj::set_", stringify!($name), "`")]
+                $variant,
+            )*
+        }
+
+        /// Names of all [`SetParam`]s as returned by [`SetParam::name`], in the same order as [`SetParam::ALL`]
+        ///
+        /// Names are stable across versions, they are only ever added to
+        pub const SETTABLE_PARAMS: &[&str] = &[$(stringify!($name),)*];
+
+        impl SetParam {
+            /// All parameters, in alphabetical order
+            pub const ALL: &'static [SetParam] = &[$(SetParam::$variant,)*];
+
+            /// Name of the parameter, the setter name without its `set_` prefix
+            pub const fn name(&self) -> &'static str {
+                match self {
+                    $(SetParam::$variant => stringify!($name),)*
+                }
+            }
+        }
+    };
+}
+
+set_params! {
+    ApuSkinTempLimit => apu_skin_temp_limit;
+    ApuSlowLimit => apu_slow_limit;
+    Coall => coall;
+    Coper => coper;
+    DgpuSkinTempLimit => dgpu_skin_temp_limit;
+    FastLimit => fast_limit;
+    GfxClk => gfx_clk;
+    MaxFclkFreq => max_fclk_freq;
+    MaxGfxclkFreq => max_gfxclk_freq;
+    MaxLclk => max_lclk;
+    MaxSocclkFreq => max_socclk_freq;
+    MaxVcn => max_vcn;
+    MinFclkFreq => min_fclk_freq;
+    MinGfxclkFreq => min_gfxclk_freq;
+    MinLclk => min_lclk;
+    MinSocclkFreq => min_socclk_freq;
+    MinVcn => min_vcn;
+    OcClk => oc_clk;
+    OcVolt => oc_volt;
+    PerCoreOcClk => per_core_oc_clk;
+    ProchotDeassertionRamp => prochot_deassertion_ramp;
+    Psi0Current => psi0_current;
+    Psi0socCurrent => psi0soc_current;
+    Psi3cpuCurrent => psi3cpu_current;
+    Psi3gfxCurrent => psi3gfx_current;
+    SkinTempPowerLimit => skin_temp_power_limit;
+    SlowLimit => slow_limit;
+    SlowTime => slow_time;
+    StapmLimit => stapm_limit;
+    StapmTime => stapm_time;
+    TctlTemp => tctl_temp;
+    VrmCurrent => vrm_current;
+    VrmcvipCurrent => vrmcvip_current;
+    VrmgfxCurrent => vrmgfx_current;
+    VrmgfxmaxCurrent => vrmgfxmax_current;
+    VrmmaxCurrent => vrmmax_current;
+    VrmsocCurrent => vrmsoc_current;
+    VrmsocmaxCurrent => vrmsocmax_current;
+}
+
+/// Parameters whose value is read back as the [`Metric`](crate::Metric) of the same name, see [`SetParam::name`] and [`Metric::name`](crate::Metric::name)
+///
+/// The remaining parameters can only be written, metrics without a parameter are listed in
+/// [`READONLY_METRICS`](crate::READONLY_METRICS)
+pub const WRITABLE_PARAMS: &[SetParam] = &[
+    SetParam::ApuSkinTempLimit,
+    SetParam::ApuSlowLimit,
+    SetParam::DgpuSkinTempLimit,
+    SetParam::FastLimit,
+    SetParam::GfxClk,
+    SetParam::Psi0Current,
+    SetParam::Psi0socCurrent,
+    SetParam::SlowLimit,
+    SetParam::SlowTime,
+    SetParam::StapmLimit,
+    SetParam::StapmTime,
+    SetParam::TctlTemp,
+    SetParam::VrmCurrent,
+    SetParam::VrmmaxCurrent,
+    SetParam::VrmsocCurrent,
+    SetParam::VrmsocmaxCurrent,
+];
+
+impl SetParam {
+    /// Returns if the family supports the parameter according to [`RyzenFamily::capabilities`]
+    ///
+    /// Unknown families support nothing
+    pub fn is_supported(&self, family: &RyzenFamily) -> bool {
+        !matches!(family, RyzenFamily::Unknow) && family.capabilities().supports(*self)
+    }
+
+    /// Returns the plausible min/max/step of the parameter, in the unit taken by its setter
+    ///
+    /// The bounds are the same on every family supporting the parameter, they are no limits of a
+    /// specific CPU and the SMU may still reject values inside of them. `family` only decides if
+    /// there is a range at all: returns `None` if the family is unknown, the parameter is not
+    /// supported by the family or it has no known bounds.
+    pub fn plausible_range(&self, family: &RyzenFamily) -> Option<ParamRange> {
+        use SetParam::*;
+
+        if !self.is_supported(family) {
+            return None;
+        }
+
+        match self {
+            Coall | Coper => Some(CURVE_OFFSET),
+            OcVolt => Some(VID),
+            GfxClk | MaxFclkFreq | MaxGfxclkFreq | MaxLclk | MaxSocclkFreq | MaxVcn
+            | MinFclkFreq | MinGfxclkFreq | MinLclk | MinSocclkFreq | MinVcn | OcClk
+            | PerCoreOcClk => Some(CLOCK_MHZ),
+            ApuSlowLimit | FastLimit | SkinTempPowerLimit | SlowLimit | StapmLimit => {
+                Some(POWER_MW)
+            }
+            Psi0Current | Psi0socCurrent | Psi3cpuCurrent | Psi3gfxCurrent | VrmCurrent
+            | VrmcvipCurrent | VrmgfxCurrent | VrmgfxmaxCurrent | VrmmaxCurrent | VrmsocCurrent
+            | VrmsocmaxCurrent => Some(CURRENT_MA),
+            ApuSkinTempLimit | DgpuSkinTempLimit | TctlTemp => Some(TEMP_C),
+            SlowTime | StapmTime => Some(TIME_S),
+            ProchotDeassertionRamp => None,
+        }
+    }
+}
+
+/// Knobs supported by a [`RyzenFamily`], returned by [`RyzenFamily::capabilities`]
+///
+/// Setters of unsupported knobs fail with [`RyzenAdjError::AdjFamilyNotSupported`](crate::RyzenAdjError::AdjFamilyNotSupported)
+/// before anything is sent to the SMU, GUIs can use this to hide them up front.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct FamilyCapabilities {
+    /// `set_coall`
+    pub curve_optimizer: bool,
+    /// `set_coper`
+    pub per_core_curve_optimizer: bool,
+    /// `set_enable_oc`, `set_oc_clk` and `set_oc_volt`
+    pub overclock: bool,
+    /// `set_per_core_oc_clk`
+    pub per_core_oc: bool,
+    /// `set_max_vcn` and `set_min_vcn`
+    pub vcn_control: bool,
+    /// `set_max_lclk` and `set_min_lclk`
+    pub lclk_control: bool,
+    /// `set_skin_temp_power_limit`
+    pub skin_temp_power_limit: bool,
+    /// `set_apu_skin_temp_limit` and `set_dgpu_skin_temp_limit`
+    pub skin_temp_limits: bool,
+    /// `set_max_gfxclk_freq`, `set_min_gfxclk_freq`, `set_max_socclk_freq`, `set_min_socclk_freq`,
+    /// `set_max_fclk_freq` and `set_min_fclk_freq`
+    pub clock_limits: bool,
+    /// `set_gfx_clk`
+    pub gfx_clk: bool,
+    /// `set_apu_slow_limit`
+    pub apu_slow_limit: bool,
+    /// `set_psi0_current` and `set_psi0soc_current`
+    pub psi0_current: bool,
+    /// `set_psi3cpu_current`, `set_psi3gfx_current` and `set_vrmcvip_current`
+    pub psi3_current: bool,
+    /// `set_vrmgfx_current` and `set_vrmgfxmax_current`
+    pub gfx_vrm_current: bool,
+    /// `set_vrmsocmax_current`
+    pub soc_max_current: bool,
+}
+
+impl SetParam {
+    /// Returns the highest value the plain `u32` setter of the parameter accepts, `None` if it does not check
+    ///
+    /// Ceilings are far above anything the SMU accepts (1kW, 1kA, 200 degree C, 1h, 10GHz, the
+    /// highest 8-bit VID), so they only reject values no caller means to set, like a subtraction that
+    /// underflowed, with [`RyzenAdjError::AdjValueOutOfRange`](crate::RyzenAdjError::AdjValueOutOfRange)
+    /// before they reach the SMU. Use the `_checked` and typed setters to check against real ranges.
+    pub fn ceiling(&self) -> Option<u32> {
+        use SetParam::*;
+
+        match self {
+            ApuSlowLimit | FastLimit | SkinTempPowerLimit | SlowLimit | StapmLimit => {
+                Some(1_000_000)
+            }
+            Psi0Current | Psi0socCurrent | Psi3cpuCurrent | Psi3gfxCurrent | VrmCurrent
+            | VrmcvipCurrent | VrmgfxCurrent | VrmgfxmaxCurrent | VrmmaxCurrent | VrmsocCurrent
+            | VrmsocmaxCurrent => Some(1_000_000),
+            ApuSkinTempLimit | DgpuSkinTempLimit | TctlTemp => Some(200),
+            SlowTime | StapmTime => Some(3600),
+            GfxClk | MaxFclkFreq | MaxGfxclkFreq | MaxLclk | MaxSocclkFreq | MaxVcn
+            | MinFclkFreq | MinGfxclkFreq | MinLclk | MinSocclkFreq | MinVcn | OcClk => {
+                Some(10_000)
+            }
+            OcVolt => Some(u8::MAX.into()),
+            // curve offsets are range checked, the others are encoded values without a known bound
+            Coall | Coper | PerCoreOcClk | ProchotDeassertionRamp => None,
+        }
+    }
+}
+
+/// Rejects values above the [`SetParam::ceiling`] of the parameter
+pub(crate) fn check_ceiling(param: SetParam, value: u32) -> RyzenAdjResult<()> {
+    match param.ceiling() {
+        Some(ceiling) => RyzenAdj::check_range(value.into(), 0, ceiling.into()),
+        None => Ok(()),
+    }
+}
+
+impl FamilyCapabilities {
+    /// Returns if the setter of `param` is covered by a supported knob
+    ///
+    /// The PPT limits and times, tctl temperature, main VRM currents and prochot ramp are covered by no
+    /// knob, ryzenadj sets them on every family it supports
+    pub fn supports(&self, param: SetParam) -> bool {
+        use SetParam::*;
+
+        match param {
+            Coall => self.curve_optimizer,
+            Coper => self.per_core_curve_optimizer,
+            OcClk | OcVolt => self.overclock,
+            PerCoreOcClk => self.per_core_oc,
+            MaxVcn | MinVcn => self.vcn_control,
+            MaxLclk | MinLclk => self.lclk_control,
+            SkinTempPowerLimit => self.skin_temp_power_limit,
+            ApuSkinTempLimit | DgpuSkinTempLimit => self.skin_temp_limits,
+            MaxFclkFreq | MaxGfxclkFreq | MaxSocclkFreq | MinFclkFreq | MinGfxclkFreq
+            | MinSocclkFreq => self.clock_limits,
+            GfxClk => self.gfx_clk,
+            ApuSlowLimit => self.apu_slow_limit,
+            Psi0Current | Psi0socCurrent => self.psi0_current,
+            Psi3cpuCurrent | Psi3gfxCurrent | VrmcvipCurrent => self.psi3_current,
+            VrmgfxCurrent | VrmgfxmaxCurrent => self.gfx_vrm_current,
+            VrmsocmaxCurrent => self.soc_max_current,
+            FastLimit
+            | ProchotDeassertionRamp
+            | SlowLimit
+            | SlowTime
+            | StapmLimit
+            | StapmTime
+            | TctlTemp
+            | VrmCurrent
+            | VrmmaxCurrent
+            | VrmsocCurrent => true,
+        }
+    }
+}
+
+impl RyzenAdj {
+    /// Gets all parameters the detected family supports, in [`SetParam::ALL`] order
+    ///
+    /// See [`SetParam::is_supported`], GUIs can use this to only show controls that can work
+    pub fn supported_params(&self) -> RyzenAdjResult<Vec<SetParam>> {
+        let family = self.get_cpu_family()?;
+        Ok(SetParam::ALL
+            .iter()
+            .copied()
+            .filter(|param| param.is_supported(&family))
+            .collect())
+    }
+}
+
+impl RyzenFamily {
+    /// Returns the knobs ryzenadj supports on this family
+    ///
+    /// Each knob lists the families of the switch in its setters in ryzenadj's lib/api.c, which has no
+    /// case for Strix Point in any of them. Unknown families support nothing.
+    pub fn capabilities(&self) -> FamilyCapabilities {
+        use RyzenFamily::*;
+
+        let raven = matches!(self, Raven | Picassso | Dali);
+        let skin = matches!(
+            self,
+            Renoir | Lucienne | Cezanne | Vangogh | Rembrandt | Mendocino | Phoenix | Hawkpoint
+        );
+        FamilyCapabilities {
+            curve_optimizer: matches!(
+                self,
+                Renoir | Lucienne | Cezanne | Rembrandt | Vangogh | Phoenix | Hawkpoint
+            ),
+            per_core_curve_optimizer: matches!(
+                self,
+                Renoir | Lucienne | Cezanne | Rembrandt | Phoenix | Hawkpoint
+            ),
+            overclock: matches!(self, Renoir | Lucienne | Cezanne | Rembrandt),
+            per_core_oc: matches!(self, Renoir | Lucienne | Cezanne | Rembrandt),
+            vcn_control: raven,
+            lclk_control: raven,
+            skin_temp_power_limit: skin,
+            skin_temp_limits: skin,
+            clock_limits: raven,
+            gfx_clk: skin,
+            apu_slow_limit: matches!(
+                self,
+                Renoir | Lucienne | Cezanne | Rembrandt | Phoenix | Hawkpoint
+            ),
+            psi0_current: raven || matches!(self, Renoir | Lucienne | Cezanne),
+            psi3_current: matches!(self, Vangogh),
+            gfx_vrm_current: matches!(self, Vangogh),
+            soc_max_current: raven
+                || matches!(
+                    self,
+                    Renoir | Lucienne | Cezanne | Rembrandt | Mendocino | Phoenix | Hawkpoint
+                ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Families listed in the switch of each knob's setter in ryzenadj's lib/api.c
+    fn upstream_families(knob: &str) -> &'static [RyzenFamily] {
+        use RyzenFamily::*;
+
+        match knob {
+            "coall" => &[
+                Renoir, Lucienne, Cezanne, Rembrandt, Vangogh, Phoenix, Hawkpoint,
+            ],
+            "coper" => &[Renoir, Lucienne, Cezanne, Rembrandt, Phoenix, Hawkpoint],
+            "oc" | "per_core_oc" => &[Renoir, Lucienne, Cezanne, Rembrandt],
+            "vcn" | "lclk" => &[Raven, Picassso, Dali],
+            "skin" | "skin_temp" => &[
+                Renoir, Lucienne, Cezanne, Vangogh, Rembrandt, Mendocino, Phoenix, Hawkpoint,
+            ],
+            "gfxclk_freq" | "socclk_freq" | "fclk_freq" => &[Raven, Picassso, Dali],
+            "gfx_clk" => &[
+                Renoir, Lucienne, Cezanne, Vangogh, Rembrandt, Mendocino, Phoenix, Hawkpoint,
+            ],
+            "apu_slow" => &[Renoir, Lucienne, Cezanne, Rembrandt, Phoenix, Hawkpoint],
+            "psi0" => &[Raven, Picassso, Renoir, Cezanne, Dali, Lucienne],
+            "psi3" | "vrmcvip" | "vrmgfx" => &[Vangogh],
+            "vrmsocmax" => &[
+                Raven, Picassso, Renoir, Cezanne, Dali, Lucienne, Rembrandt, Mendocino, Phoenix,
+                Hawkpoint,
+            ],
+            _ => unreachable!(),
+        }
+    }
+
+    const FAMILIES: &[RyzenFamily] = &[
+        RyzenFamily::Raven,
+        RyzenFamily::Picassso,
+        RyzenFamily::Renoir,
+        RyzenFamily::Cezanne,
+        RyzenFamily::Dali,
+        RyzenFamily::Lucienne,
+        RyzenFamily::Vangogh,
+        RyzenFamily::Rembrandt,
+        RyzenFamily::Mendocino,
+        RyzenFamily::Phoenix,
+        RyzenFamily::Hawkpoint,
+        RyzenFamily::Strixpoint,
+    ];
+
+    #[test]
+    fn capabilities_match_upstream_family_lists() {
+        for family in FAMILIES {
+            let caps = family.capabilities();
+            let knobs = [
+                ("coall", caps.curve_optimizer),
+                ("coper", caps.per_core_curve_optimizer),
+                ("oc", caps.overclock),
+                ("per_core_oc", caps.per_core_oc),
+                ("vcn", caps.vcn_control),
+                ("lclk", caps.lclk_control),
+                ("skin", caps.skin_temp_power_limit),
+                ("skin_temp", caps.skin_temp_limits),
+                ("gfxclk_freq", caps.clock_limits),
+                ("socclk_freq", caps.clock_limits),
+                ("fclk_freq", caps.clock_limits),
+                ("gfx_clk", caps.gfx_clk),
+                ("apu_slow", caps.apu_slow_limit),
+                ("psi0", caps.psi0_current),
+                ("psi3", caps.psi3_current),
+                ("vrmcvip", caps.psi3_current),
+                ("vrmgfx", caps.gfx_vrm_current),
+                ("vrmsocmax", caps.soc_max_current),
+            ];
+            for (knob, supported) in knobs {
+                assert_eq!(
+                    supported,
+                    upstream_families(knob).contains(family),
+                    "{knob} on {family:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn settable_params_match_set_param() {
+        let names: Vec<&str> = SetParam::ALL.iter().map(SetParam::name).collect();
+        assert_eq!(names, SETTABLE_PARAMS);
+
+        let mut sorted = names.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+        assert_eq!(sorted, names, "SetParam::ALL must be sorted and unique");
+    }
+
+    #[test]
+    fn settable_params_have_setters() {
+        let source = include_str!("lib.rs");
+        for name in SETTABLE_PARAMS {
+            let setter = format!("pub fn set_{name}(&self, ");
+            assert!(source.contains(&setter), "no `{setter}` in lib.rs");
+        }
+        // and every value taking sys setter has a parameter, the raw cogfx only has an unsafe wrapper
+        for call in source.split("libryzenadj_sys::set_").skip(1) {
+            if let Some((name, args)) = call.split_once('(') {
+                if args.starts_with("self.ryzen_adj, value") && name != "cogfx" {
+                    assert!(SETTABLE_PARAMS.contains(&name), "{name} is not a SetParam");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn strixpoint_supports_no_knob() {
+        assert_eq!(
+            RyzenFamily::Strixpoint.capabilities(),
+            FamilyCapabilities::default()
+        );
+        assert!(!SetParam::Coall.is_supported(&RyzenFamily::Strixpoint));
+        assert!(!SetParam::Coper.is_supported(&RyzenFamily::Strixpoint));
+        assert!(SetParam::StapmLimit.is_supported(&RyzenFamily::Strixpoint));
+    }
+
+    #[test]
+    fn unknown_family_supports_nothing() {
+        assert_eq!(
+            RyzenFamily::Unknow.capabilities(),
+            FamilyCapabilities::default()
+        );
+        assert!(SetParam::ALL
+            .iter()
+            .all(|param| !param.is_supported(&RyzenFamily::Unknow)));
+    }
+
+    #[test]
+    fn supports_maps_knobs_to_params() {
+        let caps = RyzenFamily::Raven.capabilities();
+        assert!(!caps.supports(SetParam::Coall));
+        assert!(!caps.supports(SetParam::OcVolt));
+        assert!(caps.supports(SetParam::MaxVcn));
+        assert!(caps.supports(SetParam::MinLclk));
+        assert!(caps.supports(SetParam::MaxGfxclkFreq));
+        assert!(caps.supports(SetParam::MinGfxclkFreq));
+        assert!(!caps.supports(SetParam::GfxClk));
+        assert!(!caps.supports(SetParam::ApuSlowLimit));
+        assert!(!caps.supports(SetParam::Psi3cpuCurrent));
+        // no knob covers the PPT limits
+        assert!(caps.supports(SetParam::StapmLimit));
+
+        let caps = RyzenFamily::Vangogh.capabilities();
+        assert!(caps.supports(SetParam::Psi3cpuCurrent));
+        assert!(caps.supports(SetParam::Psi3gfxCurrent));
+        assert!(caps.supports(SetParam::VrmcvipCurrent));
+        assert!(caps.supports(SetParam::GfxClk));
+        assert!(!caps.supports(SetParam::ApuSlowLimit));
+        assert!(!caps.supports(SetParam::MaxGfxclkFreq));
+
+        let caps = RyzenFamily::Hawkpoint.capabilities();
+        assert!(caps.supports(SetParam::ApuSlowLimit));
+        assert!(!caps.supports(SetParam::VrmcvipCurrent));
+        assert_eq!(SetParam::Coall.plausible_range(&RyzenFamily::Raven), None);
+        assert_eq!(
+            SetParam::Coall.plausible_range(&RyzenFamily::Renoir),
+            Some(CURVE_OFFSET)
+        );
+    }
+}
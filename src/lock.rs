@@ -0,0 +1,114 @@
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{RyzenAdjError, RyzenAdjResult};
+
+/// How often a held lock is retried until the timeout passes
+const RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Name of the lockfile used by [`RyzenAdjBuilder::smu_lock`](crate::RyzenAdjBuilder::smu_lock), in [`SMU_LOCK_DIR`]
+pub const SMU_LOCK_FILE: &str = "ryzenadj.lock";
+
+/// Directory of [`SMU_LOCK_FILE`], [`std::env::temp_dir`] is used on systems without it
+pub const SMU_LOCK_DIR: &str = "/run/lock";
+
+/// Path of the lockfile used by [`RyzenAdjBuilder::smu_lock`](crate::RyzenAdjBuilder::smu_lock)
+pub(crate) fn default_lock_path() -> PathBuf {
+    let dir = Path::new(SMU_LOCK_DIR);
+    if dir.is_dir() {
+        dir.join(SMU_LOCK_FILE)
+    } else {
+        std::env::temp_dir().join(SMU_LOCK_FILE)
+    }
+}
+
+/// Advisory lock on a lockfile, held around every SMU access of an instance
+#[derive(Debug, Clone)]
+pub(crate) struct SmuLock {
+    pub(crate) path: PathBuf,
+    pub(crate) timeout: Duration,
+}
+
+impl SmuLock {
+    /// Locks the lockfile, the lock is held until the returned file is dropped
+    ///
+    /// Fails with [`RyzenAdjError::SmuBusy`] if the lock is not free within the timeout,
+    /// and with [`RyzenAdjError::Io`] if the lockfile can not be opened or locked
+    pub(crate) fn acquire(&self) -> RyzenAdjResult<File> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&self.path)?;
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            if try_lock(&file)? {
+                return Ok(file);
+            } else if Instant::now() < deadline {
+                thread::sleep(RETRY_INTERVAL)
+            } else {
+                return Err(RyzenAdjError::SmuBusy);
+            }
+        }
+    }
+}
+
+/// Takes an exclusive `flock` on `file` without blocking, returns `false` if it is held elsewhere
+///
+/// The lock belongs to the open file, so it is released when `file` is closed, and separately opened
+/// files of the same process exclude each other like other processes do
+#[cfg(unix)]
+fn try_lock(file: &File) -> io::Result<bool> {
+    use std::os::unix::io::AsRawFd;
+
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0 {
+        return Ok(true);
+    }
+    let err = io::Error::last_os_error();
+    match err.raw_os_error() {
+        Some(libc::EWOULDBLOCK) | Some(libc::EINTR) => Ok(false),
+        _ => Err(err),
+    }
+}
+
+/// Fails, the SMU lock is only implemented with `flock` on unix
+#[cfg(not(unix))]
+fn try_lock(_file: &File) -> io::Result<bool> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lock_at(path: PathBuf) -> SmuLock {
+        SmuLock {
+            path,
+            timeout: Duration::from_millis(50),
+        }
+    }
+
+    #[test]
+    fn unopenable_lockfile_is_io_error() {
+        let lock = lock_at(
+            std::env::temp_dir()
+                .join("ryzenadj-missing-dir")
+                .join(SMU_LOCK_FILE),
+        );
+        assert!(matches!(lock.acquire(), Err(RyzenAdjError::Io(_))));
+    }
+
+    #[test]
+    fn held_lock_is_busy_until_released() {
+        let path = std::env::temp_dir().join(format!("ryzenadj-test-{}.lock", std::process::id()));
+        let lock = lock_at(path.clone());
+        let held = lock.acquire().expect("free lock");
+        assert!(matches!(lock.acquire(), Err(RyzenAdjError::SmuBusy)));
+        drop(held);
+        assert!(lock.acquire().is_ok());
+        let _ = std::fs::remove_file(path);
+    }
+}
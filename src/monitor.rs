@@ -0,0 +1,364 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::snapshot::available;
+use crate::{PowerSnapshot, RyzenAdj, RyzenAdjError, RyzenAdjResult, SetParam};
+
+/// How long [`RyzenAdj::self_test`] waits for the changed limit to show up in the table
+const SELF_TEST_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Time between two reads of the changed limit in [`RyzenAdj::self_test`]
+const SELF_TEST_POLL: Duration = Duration::from_millis(100);
+
+/// Resets the all core curve optimiser to 0 when dropped, so a panicking check of
+/// [`RyzenAdj::probe_curve_offset`] does not leave the probed offset applied
+struct CurveOffsetReset<'a> {
+    ryzen_adj: &'a RyzenAdj,
+    armed: bool,
+}
+
+impl CurveOffsetReset<'_> {
+    /// Resets the offset, returning the error of the reset
+    fn reset(mut self) -> RyzenAdjResult<()> {
+        self.armed = false;
+        self.ryzen_adj.set_coall(0)
+    }
+}
+
+impl Drop for CurveOffsetReset<'_> {
+    fn drop(&mut self) {
+        if self.armed {
+            let _ = self.ryzen_adj.set_coall(0);
+        }
+    }
+}
+
+/// Runs [`RyzenAdj::self_test`] with `read` refreshing and reading the tctl limit and `write` setting it
+///
+/// The test value is read back every `poll` until it shows up or `timeout` passes, values read back
+/// are rounded to whole degrees before comparing
+fn run_self_test(
+    mut read: impl FnMut() -> RyzenAdjResult<Option<f32>>,
+    mut write: impl FnMut(u32) -> RyzenAdjResult<()>,
+    poll: Duration,
+    timeout: Duration,
+) -> RyzenAdjResult<SelfTestReport> {
+    let mut read_limit = || read().map(|limit| limit.map(|limit| limit.round() as u32));
+    let original = read_limit()?
+        .filter(|&limit| limit > 0)
+        .ok_or(RyzenAdjError::GetNaN)?;
+    let test_value = original - 1;
+
+    let mut poll_test_value = || -> RyzenAdjResult<Option<u32>> {
+        write(test_value)?;
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            thread::sleep(poll.min(remaining));
+            let read_back = read_limit()?;
+            if read_back == Some(test_value) || remaining.is_zero() {
+                return Ok(read_back);
+            }
+        }
+    };
+    let result = poll_test_value();
+    let restore = write(original);
+    let read_back = result?;
+    restore?;
+    Ok(SelfTestReport {
+        param: SetParam::TctlTemp,
+        original,
+        test_value,
+        read_back,
+        effective: read_back == Some(test_value),
+    })
+}
+
+/// Result of [`RyzenAdj::self_test`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelfTestReport {
+    /// the limit that was changed
+    pub param: SetParam,
+    /// value of the limit before the test, restored afterwards
+    pub original: u32,
+    /// value written during the test
+    pub test_value: u32,
+    /// last value read back during the test, `None` if the limit stopped being reported
+    pub read_back: Option<u32>,
+    /// the test value was read back, so setters change the limits on this machine
+    pub effective: bool,
+}
+
+impl RyzenAdj {
+    /// Blocks until the tctl temperature drops below `celsius`
+    ///
+    /// The table is refreshed every `poll` and checked against `get_tctl_temp_value`, the current temperature
+    /// (`get_tctl_temp` is the limit). Returns `false` if `timeout` passed first. A NaN temperature returns
+    /// [`RyzenAdjError::GetNaN`](crate::RyzenAdjError::GetNaN) instead of waiting for the timeout.
+    pub fn wait_until_temp_below(
+        &self,
+        celsius: f32,
+        poll: Duration,
+        timeout: Duration,
+    ) -> RyzenAdjResult<bool> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            self.refresh()?;
+            // a NaN must not read as 0 degree C with NanPolicy::Zero
+            if self.ignoring_nan_policy(|| self.get_tctl_temp_value())? < celsius {
+                return Ok(true);
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(false);
+            }
+            thread::sleep(poll.min(remaining));
+        }
+    }
+
+    /// Returns the average socket power in W over `window`, sampled every `poll`
+    ///
+    /// **Blocks for the whole `window`.** The table is refreshed before each sample of `get_socket_power`,
+    /// at least one sample is always taken and NaN samples are skipped. Returns
+    /// [`RyzenAdjError::GetNaN`] if no sample was available.
+    pub fn average_power(&self, window: Duration, poll: Duration) -> RyzenAdjResult<f32> {
+        let deadline = Instant::now() + window;
+        let mut sum = 0.0;
+        let mut samples = 0u32;
+        loop {
+            self.refresh()?;
+            // NaN samples are skipped with NanPolicy::Zero too, instead of averaging in zeros
+            if let Some(power) = self.ignoring_nan_policy(|| available(self.get_socket_power()))? {
+                sum += f64::from(power);
+                samples += 1;
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+            thread::sleep(poll.min(remaining));
+        }
+        if samples == 0 {
+            return Err(RyzenAdjError::GetNaN);
+        }
+        Ok((sum / f64::from(samples)) as f32)
+    }
+
+    /// Checks if setters actually change limits, as on some locked down systems they succeed without effect
+    ///
+    /// **This briefly perturbs a limit:** the tctl temperature limit is lowered by 1 degree C, read back
+    /// for up to a second and then restored, also on errors. Lowering a temperature limit only makes the
+    /// CPU throttle earlier, so this is harmless, but it races with other tools setting the same limit.
+    /// Fails with [`RyzenAdjError::GetNaN`] if the tctl limit is not reported (or 0), as there is
+    /// nothing to verify against then.
+    pub fn self_test(&self) -> RyzenAdjResult<SelfTestReport> {
+        run_self_test(
+            || {
+                self.refresh()?;
+                self.ignoring_nan_policy(|| available(self.get_tctl_temp()))
+            },
+            |value| self.set_tctl_temp(value),
+            SELF_TEST_POLL,
+            SELF_TEST_TIMEOUT,
+        )
+    }
+
+    /// Steps the all core curve optimiser from `start` by `step` until `check` fails, returning the last offset that passed
+    ///
+    /// After setting each offset this waits `stabilize`, takes a snapshot and passes it to `check`.
+    /// Probing stops at the first failed check or once the offset leaves -30..=30 (or the range set with
+    /// [`RyzenAdjBuilder::max_curve_offset`](crate::RyzenAdjBuilder::max_curve_offset)), and returns 0
+    /// if already `start` fails. The offset is always reset to 0 afterwards, also on errors and when
+    /// `check` panics, apply the returned one with [`RyzenAdj::set_coall`].
+    ///
+    /// **Use at your own risk:** an unstable offset usually crashes or freezes the system long before
+    /// any value in a snapshot looks wrong, use small steps and a `check` that runs an actual workload.
+    pub fn probe_curve_offset(
+        &self,
+        start: i32,
+        step: i32,
+        stabilize: Duration,
+        check: impl Fn(&PowerSnapshot) -> bool,
+    ) -> RyzenAdjResult<i32> {
+        let reset = CurveOffsetReset {
+            ryzen_adj: self,
+            armed: true,
+        };
+        let result = self.probe_curve_offset_inner(start, step, stabilize, check);
+        let reset = reset.reset();
+        let stable = result?;
+        reset?;
+        Ok(stable)
+    }
+
+    fn probe_curve_offset_inner(
+        &self,
+        start: i32,
+        step: i32,
+        stabilize: Duration,
+        check: impl Fn(&PowerSnapshot) -> bool,
+    ) -> RyzenAdjResult<i32> {
+        let mut stable = 0;
+        let mut offset = Some(start);
+        while let Some(current) =
+            offset.filter(|&offset| self.curve_offset.check(offset.into()).is_ok())
+        {
+            self.set_coall(current)?;
+            thread::sleep(stabilize);
+            if !check(&self.snapshot()?) {
+                break;
+            }
+            stable = current;
+            offset = if step == 0 {
+                None
+            } else {
+                current.checked_add(step)
+            };
+        }
+        Ok(stable)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+
+    use super::*;
+
+    const POLL: Duration = Duration::from_millis(1);
+    const TIMEOUT: Duration = Duration::from_millis(20);
+
+    /// A tctl limit taking effect after `delay` reads, `None` never takes effect
+    struct MockLimit {
+        limit: Cell<f32>,
+        pending: Cell<Option<(f32, u32)>>,
+        delay: Option<u32>,
+        writes: RefCell<Vec<u32>>,
+    }
+
+    impl MockLimit {
+        fn new(limit: f32, delay: Option<u32>) -> Self {
+            MockLimit {
+                limit: Cell::new(limit),
+                pending: Cell::new(None),
+                delay,
+                writes: RefCell::new(Vec::new()),
+            }
+        }
+
+        fn read(&self) -> RyzenAdjResult<Option<f32>> {
+            if let Some((value, reads)) = self.pending.get() {
+                if reads == 0 {
+                    self.limit.set(value);
+                    self.pending.set(None);
+                } else {
+                    self.pending.set(Some((value, reads - 1)));
+                }
+            }
+            Ok(Some(self.limit.get()))
+        }
+
+        fn write(&self, value: u32) -> RyzenAdjResult<()> {
+            self.writes.borrow_mut().push(value);
+            if let Some(delay) = self.delay {
+                self.pending.set(Some((value as f32, delay)));
+            }
+            Ok(())
+        }
+
+        fn run(&self) -> RyzenAdjResult<SelfTestReport> {
+            run_self_test(|| self.read(), |value| self.write(value), POLL, TIMEOUT)
+        }
+    }
+
+    #[test]
+    fn effective_setter_is_read_back_and_restored() {
+        let mock = MockLimit::new(95.0, Some(0));
+        let report = mock.run().unwrap();
+        assert_eq!(
+            report,
+            SelfTestReport {
+                param: SetParam::TctlTemp,
+                original: 95,
+                test_value: 94,
+                read_back: Some(94),
+                effective: true,
+            }
+        );
+        assert_eq!(*mock.writes.borrow(), [94, 95]);
+    }
+
+    #[test]
+    fn delayed_readback_is_waited_for() {
+        let report = MockLimit::new(95.0, Some(3)).run().unwrap();
+        assert!(report.effective);
+        assert_eq!(report.read_back, Some(94));
+    }
+
+    #[test]
+    fn ignored_setter_is_not_effective() {
+        let mock = MockLimit::new(95.0, None);
+        let report = mock.run().unwrap();
+        assert!(!report.effective);
+        assert_eq!(report.read_back, Some(95));
+        assert_eq!(*mock.writes.borrow(), [94, 95]);
+    }
+
+    /// Runs the self test reading `original` first and `read_back` afterwards
+    fn run_with_readback(original: f32, read_back: f32) -> SelfTestReport {
+        let reads = Cell::new(0);
+        let read = || {
+            reads.set(reads.get() + 1);
+            Ok(Some(if reads.get() == 1 {
+                original
+            } else {
+                read_back
+            }))
+        };
+        run_self_test(read, |_| Ok(()), POLL, TIMEOUT).unwrap()
+    }
+
+    #[test]
+    fn readback_is_rounded_to_whole_degrees() {
+        let report = run_with_readback(95.4, 93.6);
+        assert_eq!(report.original, 95);
+        assert_eq!(report.read_back, Some(94));
+        assert!(report.effective);
+
+        let report = run_with_readback(95.4, 93.4);
+        assert_eq!(report.read_back, Some(93));
+        assert!(!report.effective);
+    }
+
+    #[test]
+    fn unreported_limit_is_nan_error() {
+        for limit in [None, Some(0.0), Some(0.4)] {
+            let result = run_self_test(|| Ok(limit), |_| Ok(()), POLL, TIMEOUT);
+            assert!(matches!(result, Err(RyzenAdjError::GetNaN)), "{limit:?}");
+        }
+    }
+
+    #[test]
+    fn failing_readback_still_restores() {
+        let writes = RefCell::new(Vec::new());
+        let reads = Cell::new(0);
+        let result = run_self_test(
+            || {
+                reads.set(reads.get() + 1);
+                if reads.get() == 1 {
+                    Ok(Some(95.0))
+                } else {
+                    Err(RyzenAdjError::AdjSmuTimeout)
+                }
+            },
+            |value| {
+                writes.borrow_mut().push(value);
+                Ok(())
+            },
+            POLL,
+            TIMEOUT,
+        );
+        assert!(matches!(result, Err(RyzenAdjError::AdjSmuTimeout)));
+        assert_eq!(*writes.borrow(), [94, 95]);
+    }
+}
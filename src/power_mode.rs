@@ -0,0 +1,139 @@
+use crate::{Profile, RyzenAdj, RyzenAdjError, RyzenAdjResult, RyzenFamily, SetParam};
+
+/// Power modes handhelds and laptops usually switch between on AC events, see [`RyzenAdj::apply_power_mode`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PowerMode {
+    /// 50% of the power budget, with overclocking disabled
+    Battery,
+    /// 75% of the power budget, with overclocking disabled
+    Balanced,
+    /// the full power budget, overclocking is left as is
+    Turbo,
+}
+
+impl PowerMode {
+    /// Share of the power budget in percent, see [`RyzenAdj::set_power_budget_percent`]
+    fn budget_percent(&self) -> u8 {
+        match self {
+            PowerMode::Battery => 50,
+            PowerMode::Balanced => 75,
+            PowerMode::Turbo => 100,
+        }
+    }
+}
+
+/// Everything written by [`RyzenAdj::apply_power_mode_settings`], fields left at `None` are not touched
+///
+/// Get the settings of a [`PowerMode`] with [`RyzenAdj::power_mode_settings`] and change them to
+/// override the built in modes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PowerModeSettings {
+    /// limits applied like [`RyzenAdj::apply_profile`]
+    pub profile: Profile,
+    /// `Some(true)` calls [`RyzenAdj::set_enable_oc`], `Some(false)` [`RyzenAdj::set_disable_oc`]
+    pub oc: Option<bool>,
+    /// Skin Temperature Power Limit (mW), see [`RyzenAdj::set_skin_temp_power_limit`]
+    pub skin_temp_power_limit: Option<u32>,
+}
+
+impl PowerModeSettings {
+    /// Settings of `mode` on `family` given the scaled (STAPM, fast, slow) limits in mW
+    fn new(mode: PowerMode, family: RyzenFamily, (stapm, fast, slow): (u32, u32, u32)) -> Self {
+        let caps = family.capabilities();
+        PowerModeSettings {
+            profile: Profile {
+                stapm_limit: Some(stapm),
+                fast_limit: Some(fast),
+                slow_limit: Some(slow),
+                ..Profile::default()
+            },
+            oc: (mode != PowerMode::Turbo && caps.overclock).then_some(false),
+            skin_temp_power_limit: caps.skin_temp_power_limit.then_some(stapm),
+        }
+    }
+}
+
+impl RyzenAdj {
+    /// Returns the settings [`RyzenAdj::apply_power_mode`] applies for `mode` on this family
+    ///
+    /// The STAPM, fast and slow PPT limits are scaled like [`RyzenAdj::set_power_budget_percent`], so
+    /// [`PowerMode::Turbo`] restores the limits of the device and never goes above them. The skin
+    /// temperature power limit follows the STAPM limit, and overclocking is disabled outside of turbo,
+    /// both only on families supporting them. Fails with [`RyzenAdjError::AdjFamilyNotSupported`] if
+    /// the family has no known PPT ranges.
+    pub fn power_mode_settings(&self, mode: PowerMode) -> RyzenAdjResult<PowerModeSettings> {
+        let family = self.get_cpu_family()?;
+        let budget = self.power_budget(mode.budget_percent())?;
+        Ok(PowerModeSettings::new(mode, family, budget))
+    }
+
+    /// Applies the settings of [`RyzenAdj::power_mode_settings`] for `mode`
+    ///
+    /// See [`RyzenAdj::apply_power_mode_settings`] for the order and the errors
+    pub fn apply_power_mode(&self, mode: PowerMode) -> RyzenAdjResult<()> {
+        self.apply_power_mode_settings(&self.power_mode_settings(mode)?)
+    }
+
+    /// Applies power mode settings, overclocking first and the skin temperature power limit last
+    ///
+    /// Stops at the first failing setter and returns [`RyzenAdjError::ApplyFailed`] naming it, the
+    /// settings before it stay applied
+    pub fn apply_power_mode_settings(&self, settings: &PowerModeSettings) -> RyzenAdjResult<()> {
+        let failed = |field| {
+            move |err| RyzenAdjError::ApplyFailed {
+                field,
+                source: Box::new(err),
+            }
+        };
+        match settings.oc {
+            Some(true) => self.set_enable_oc().map_err(failed("enable_oc"))?,
+            Some(false) => self.set_disable_oc().map_err(failed("disable_oc"))?,
+            None => {}
+        }
+        self.apply_profile_fields(&settings.profile)?;
+        if let Some(value) = settings.skin_temp_power_limit {
+            self.set_skin_temp_power_limit(value)
+                .map_err(failed(SetParam::SkinTempPowerLimit.name()))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUDGET: (u32, u32, u32) = (15000, 20000, 18000);
+
+    #[test]
+    fn raven_leaves_out_skin_limit_and_oc() {
+        let settings = PowerModeSettings::new(PowerMode::Battery, RyzenFamily::Raven, BUDGET);
+        assert_eq!(settings.skin_temp_power_limit, None);
+        assert_eq!(settings.oc, None);
+        assert_eq!(settings.profile.stapm_limit, Some(15000));
+        assert_eq!(settings.profile.fast_limit, Some(20000));
+        assert_eq!(settings.profile.slow_limit, Some(18000));
+    }
+
+    #[test]
+    fn skin_limit_follows_stapm_where_supported() {
+        for family in [
+            RyzenFamily::Renoir,
+            RyzenFamily::Vangogh,
+            RyzenFamily::Mendocino,
+        ] {
+            let settings = PowerModeSettings::new(PowerMode::Balanced, family, BUDGET);
+            assert_eq!(settings.skin_temp_power_limit, Some(15000), "{family:?}");
+        }
+        let settings = PowerModeSettings::new(PowerMode::Balanced, RyzenFamily::Strixpoint, BUDGET);
+        assert_eq!(settings.skin_temp_power_limit, None);
+    }
+
+    #[test]
+    fn oc_disabled_outside_turbo() {
+        let cezanne = |mode| PowerModeSettings::new(mode, RyzenFamily::Cezanne, BUDGET).oc;
+        assert_eq!(cezanne(PowerMode::Battery), Some(false));
+        assert_eq!(cezanne(PowerMode::Balanced), Some(false));
+        assert_eq!(cezanne(PowerMode::Turbo), None);
+    }
+}
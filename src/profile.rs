@@ -0,0 +1,546 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::snapshot::available;
+use crate::{RyzenAdj, RyzenAdjBuilder, RyzenAdjError, RyzenAdjResult};
+
+/// Schema version of serialized profiles, written as their `version` field
+///
+/// Profiles without a `version` field are read as version 1. Deserializing a profile with a newer
+/// version than this fails instead of silently dropping settings it does not know about.
+#[cfg(feature = "serde")]
+pub const PROFILE_VERSION: u32 = 1;
+
+/// Relative difference up to which [`RyzenAdj::apply_profile_delta`] considers a limit unchanged
+pub const DELTA_TOLERANCE: f32 = 0.01;
+
+/// Converts a value read from the table into the unit taken by the matching setter
+fn to_setter_unit(value: f32, scale: f32) -> u32 {
+    (value * scale).round() as u32
+}
+
+/// Checks if `current` is within [`DELTA_TOLERANCE`] of `target`, but at least one unit
+fn within_tolerance(current: u32, target: u32) -> bool {
+    let tolerance = (target as f32 * DELTA_TOLERANCE).max(1.0);
+    (current as f32 - target as f32).abs() <= tolerance
+}
+
+macro_rules! profile {
+    ($($field:ident: $setter:ident, $getter:ident, $scale:literal, $doc:literal;)*) => {
+        /// A set of limits that can be applied at once, fields left at `None` are not touched
+        ///
+        /// Values use the same units as the matching `set_*` functions
+        ///
+        /// With the `serde` feature profiles serialize as a flat map of the set fields plus a
+        /// `version`, see [`PROFILE_VERSION`]
+        #[derive(Debug, Clone, Default, PartialEq, Eq)]
+        #[cfg_attr(
+            feature = "serde",
+            derive(serde::Serialize, serde::Deserialize),
+            serde(try_from = "ProfileRepr", into = "ProfileRepr")
+        )]
+        pub struct Profile {
+            $(
+                #[doc = $doc]
+                pub $field: Option<u32>,
+            )*
+        }
+
+        impl Profile {
+            /// Returns if any limit is set in only one of the profiles or differs by more than [`DELTA_TOLERANCE`]
+            fn differs(&self, other: &Profile) -> bool {
+                false $(
+                    || match (self.$field, other.$field) {
+                        (Some(a), Some(b)) => !within_tolerance(a, b),
+                        (None, None) => false,
+                        _ => true,
+                    }
+                )*
+            }
+        }
+
+        /// Serialized form of [`Profile`], older schema versions are migrated in [`migrate`]
+        #[cfg(feature = "serde")]
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct ProfileRepr {
+            #[serde(default = "first_version")]
+            version: u32,
+            $(
+                #[serde(default, skip_serializing_if = "Option::is_none")]
+                $field: Option<u32>,
+            )*
+        }
+
+        #[cfg(feature = "serde")]
+        impl From<Profile> for ProfileRepr {
+            fn from(profile: Profile) -> Self {
+                ProfileRepr {
+                    version: PROFILE_VERSION,
+                    $($field: profile.$field,)*
+                }
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl TryFrom<ProfileRepr> for Profile {
+            type Error = UnsupportedProfileVersion;
+
+            fn try_from(repr: ProfileRepr) -> Result<Self, Self::Error> {
+                let repr = migrate(repr)?;
+                Ok(Profile {
+                    $($field: repr.$field,)*
+                })
+            }
+        }
+
+        impl RyzenAdj {
+            /// Applies all limits set in the given profile
+            pub fn apply_profile(&self, profile: &Profile) -> RyzenAdjResult<()> {
+                $(
+                    if let Some(value) = profile.$field {
+                        self.$setter(value)?;
+                    }
+                )*
+                Ok(())
+            }
+
+            /// Applies all limits set in the given profile, wrapping the first error in
+            /// [`RyzenAdjError::ApplyFailed`] naming the field that failed
+            pub(crate) fn apply_profile_fields(&self, profile: &Profile) -> RyzenAdjResult<()> {
+                $(
+                    if let Some(value) = profile.$field {
+                        self.$setter(value).map_err(|err| RyzenAdjError::ApplyFailed {
+                            field: stringify!($field),
+                            source: Box::new(err),
+                        })?;
+                    }
+                )*
+                Ok(())
+            }
+
+            /// Refreshes the table and applies only the limits of the profile that differ from the current ones
+            ///
+            /// Readings are slightly noisy and rounded, so values within [`DELTA_TOLERANCE`] of the target
+            /// are not written again. Limits that can not be read are always written. Returns the number of writes.
+            pub fn apply_profile_delta(&self, profile: &Profile) -> RyzenAdjResult<usize> {
+                self.refresh()?;
+                let mut writes = 0;
+                $(
+                    if let Some(value) = profile.$field {
+                        let current = available(self.$getter())?;
+                        let unchanged = current
+                            .is_some_and(|current| within_tolerance(to_setter_unit(current, $scale), value));
+                        if !unchanged {
+                            self.$setter(value)?;
+                            writes += 1;
+                        }
+                    }
+                )*
+                Ok(writes)
+            }
+
+            /// Refreshes the table and reads back all limits, the ones the CPU does not report are `None`
+            ///
+            /// Values are converted to the units of the setters, so the result can be applied again
+            /// with [`RyzenAdj::apply_profile`] to restore the current state. Unreported limits are
+            /// `None` with every [`NanPolicy`](crate::NanPolicy), so they are never restored as 0.
+            pub fn current_profile(&self) -> RyzenAdjResult<Profile> {
+                self.refresh()?;
+                self.ignoring_nan_policy(|| {
+                    Ok(Profile {
+                        $($field: available(self.$getter())?.map(|value| to_setter_unit(value, $scale)),)*
+                    })
+                })
+            }
+
+            /// Refreshes the table and reads back the current value of every limit set in `fields`,
+            /// failing with [`RyzenAdjError::GetNaN`] if any of them is not reported
+            fn read_profile(&self, fields: &Profile) -> RyzenAdjResult<Profile> {
+                let current = self.current_profile()?;
+                Ok(Profile {
+                    $(
+                        $field: match fields.$field {
+                            Some(_) => Some(current.$field.ok_or(RyzenAdjError::GetNaN)?),
+                            None => None,
+                        },
+                    )*
+                })
+            }
+        }
+    };
+}
+
+profile! {
+    stapm_limit: set_stapm_limit, get_stapm_limit, 1000.0, "Sustained Power Limit - STAPM LIMIT (mW)";
+    fast_limit: set_fast_limit, get_fast_limit, 1000.0, "Actual Power Limit - PPT LIMIT FAST (mW)";
+    slow_limit: set_slow_limit, get_slow_limit, 1000.0, "Average Power Limit - PPT LIMIT SLOW (mW)";
+    stapm_time: set_stapm_time, get_stapm_time, 1.0, "STAPM constant time (s)";
+    slow_time: set_slow_time, get_slow_time, 1.0, "Slow PPT Constant Time (s)";
+    tctl_temp: set_tctl_temp, get_tctl_temp, 1.0, "Tctl Temperature Limit (degree C)";
+    apu_slow_limit: set_apu_slow_limit, get_apu_slow_limit, 1000.0, "APU PPT Slow Power limit for A+A dGPU platform (mW)";
+    apu_skin_temp_limit: set_apu_skin_temp_limit, get_apu_skin_temp_limit, 1.0, "APU skin temperature limit (degree C)";
+    vrm_current: set_vrm_current, get_vrm_current, 1000.0, "VRM Current Limit - TDC LIMIT VDD (mA)";
+    vrmmax_current: set_vrmmax_current, get_vrmmax_current, 1000.0, "VRM Maximum Current Limit - EDC LIMIT VDD (mA)";
+    vrmsoc_current: set_vrmsoc_current, get_vrmsoc_current, 1000.0, "VRM SoC Current Limit - TDC LIMIT SoC (mA)";
+    vrmsocmax_current: set_vrmsocmax_current, get_vrmsocmax_current, 1000.0, "VRM SoC Maximum Current Limit - EDC LIMIT SoC (mA)";
+}
+
+#[cfg(feature = "serde")]
+fn first_version() -> u32 {
+    1
+}
+
+/// A serialized profile was written with a schema version this crate does not know
+#[cfg(feature = "serde")]
+#[derive(Debug, thiserror::Error)]
+#[error("profile version {0} is not supported, expected 1..={PROFILE_VERSION}")]
+struct UnsupportedProfileVersion(u32);
+
+/// Upgrades a serialized profile of an older schema version to [`PROFILE_VERSION`]
+///
+/// Each version bump adds an arm moving renamed fields over, there is no older version yet.
+/// Migrations never warn, as deserializing has no way to pass warnings on next to the value: a
+/// setting that can not be carried over losslessly fails the deserialization with an error naming it,
+/// so callers see it through the error of their serde format.
+#[cfg(feature = "serde")]
+fn migrate(repr: ProfileRepr) -> Result<ProfileRepr, UnsupportedProfileVersion> {
+    match repr.version {
+        PROFILE_VERSION => Ok(repr),
+        version => Err(UnsupportedProfileVersion(version)),
+    }
+}
+
+impl RyzenAdj {
+    /// Refreshes the table and reads the limits together with the live values they limit, in the same layout
+    ///
+    /// The first profile is [`RyzenAdj::current_profile`], the second holds the matching `*_value`
+    /// reading of each limit (e.g. `get_stapm_value` as `stapm_limit`) converted to the same unit.
+    /// Fields are `None` where the CPU does not report them, `stapm_time` and `slow_time` have no live
+    /// value and are always `None` in the second profile.
+    pub fn limits_and_values(&self) -> RyzenAdjResult<(Profile, Profile)> {
+        let limits = self.current_profile()?;
+        let value = |get: fn(&RyzenAdj) -> RyzenAdjResult<f32>, scale| {
+            available(get(self)).map(|value| value.map(|value| to_setter_unit(value, scale)))
+        };
+        let values = Profile {
+            stapm_limit: value(RyzenAdj::get_stapm_value, 1000.0)?,
+            fast_limit: value(RyzenAdj::get_fast_value, 1000.0)?,
+            slow_limit: value(RyzenAdj::get_slow_value, 1000.0)?,
+            stapm_time: None,
+            slow_time: None,
+            tctl_temp: value(RyzenAdj::get_tctl_temp_value, 1.0)?,
+            apu_slow_limit: value(RyzenAdj::get_apu_slow_value, 1000.0)?,
+            apu_skin_temp_limit: value(RyzenAdj::get_apu_skin_temp_value, 1.0)?,
+            vrm_current: value(RyzenAdj::get_vrm_current_value, 1000.0)?,
+            vrmmax_current: value(RyzenAdj::get_vrmmax_current_value, 1000.0)?,
+            vrmsoc_current: value(RyzenAdj::get_vrmsoc_current_value, 1000.0)?,
+            vrmsocmax_current: value(RyzenAdj::get_vrmsocmax_current_value, 1000.0)?,
+        };
+        Ok((limits, values))
+    }
+}
+
+/// Restores the limits saved by [`RyzenAdj::scoped_limits`] when dropped
+///
+/// Restoring in `Drop` is best-effort and errors are ignored, call [`LimitGuard::restore`]
+/// to handle them.
+pub struct LimitGuard<'a> {
+    ryzen_adj: &'a RyzenAdj,
+    saved: Option<Profile>,
+}
+
+impl LimitGuard<'_> {
+    /// The limits that will be restored
+    pub fn saved(&self) -> &Profile {
+        self.saved.as_ref().expect("limits already restored")
+    }
+
+    /// Restores the saved limits, returning the first error encountered
+    pub fn restore(mut self) -> RyzenAdjResult<()> {
+        match self.saved.take() {
+            Some(saved) => self.ryzen_adj.apply_profile(&saved),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for LimitGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(saved) = self.saved.take() {
+            let _ = self.ryzen_adj.apply_profile(&saved);
+        }
+    }
+}
+
+impl RyzenAdj {
+    /// Applies the given profile until the returned guard is dropped
+    ///
+    /// The current value of every limit set in `profile` is read back and saved first,
+    /// if any of them can not be read nothing is applied.
+    pub fn scoped_limits(&self, profile: &Profile) -> RyzenAdjResult<LimitGuard<'_>> {
+        let saved = self.read_profile(profile)?;
+        let guard = LimitGuard {
+            ryzen_adj: self,
+            saved: Some(saved),
+        };
+        self.apply_profile(profile)?;
+        Ok(guard)
+    }
+}
+
+/// Background thread polling its own ryzenadj instance, stopped and joined when dropped
+struct Poller {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for Poller {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Opens a ryzenadj instance with `config` on a new thread and calls `poll` with it every `interval`,
+/// failing if the instance can not be opened
+///
+/// The instance is always opened with [`RyzenAdjBuilder::multi_open`], as the caller's instance stays open
+fn spawn_poller(
+    config: RyzenAdjBuilder,
+    interval: Duration,
+    mut poll: impl FnMut(&RyzenAdj) + Send + 'static,
+) -> RyzenAdjResult<Poller> {
+    let stop = Arc::new(AtomicBool::new(false));
+    let (opened, opened_rx) = mpsc::channel();
+    let thread = {
+        let stop = stop.clone();
+        thread::spawn(move || {
+            let ryzen_adj = match config.multi_open(true).build() {
+                Ok(ryzen_adj) => {
+                    let _ = opened.send(Ok(()));
+                    ryzen_adj
+                }
+                Err(err) => {
+                    let _ = opened.send(Err(err));
+                    return;
+                }
+            };
+            while !stop.load(Ordering::Relaxed) {
+                thread::park_timeout(interval);
+                if stop.load(Ordering::Relaxed) {
+                    break;
+                }
+                poll(&ryzen_adj);
+            }
+        })
+    };
+
+    let poller = Poller {
+        stop,
+        thread: Some(thread),
+    };
+    match opened_rx.recv() {
+        Ok(Ok(())) => Ok(poller),
+        Ok(Err(err)) => Err(err),
+        // the thread panicked before reporting back, dropping the poller joins it
+        Err(_) => Err(RyzenAdjError::PollerPanicked),
+    }
+}
+
+/// Keeps re-applying a profile from a background thread until dropped, see [`RyzenAdj::apply_profile_monitored`]
+pub struct ProfileMonitor {
+    reasserts: Arc<AtomicUsize>,
+    _poller: Poller,
+}
+
+impl ProfileMonitor {
+    /// Number of limits that had drifted and were written again so far
+    pub fn reasserts(&self) -> usize {
+        self.reasserts.load(Ordering::Relaxed)
+    }
+
+    /// Stops re-applying the profile and waits for the background thread to exit, same as dropping it
+    pub fn stop(self) {}
+}
+
+impl RyzenAdj {
+    /// Applies the given profile and re-applies every limit that drifts from it until the returned monitor is dropped
+    ///
+    /// Every `reassert_interval` the limits are read back on a background thread, and the ones changed
+    /// by other tools (e.g. OEM daemons) are written again through [`RyzenAdj::apply_profile_delta`].
+    /// The thread opens its own ryzenadj instance with the options this one was opened with, including
+    /// its SMU lock, and with [`RyzenAdjBuilder::multi_open`] so it can be open next to this one.
+    /// Errors on the background thread are ignored and
+    /// retried on the next interval. Tools fighting over the same limits keep contending for the SMU, so this
+    /// should be used with intervals of seconds rather than milliseconds.
+    pub fn apply_profile_monitored(
+        &self,
+        profile: &Profile,
+        reassert_interval: Duration,
+    ) -> RyzenAdjResult<ProfileMonitor> {
+        self.apply_profile(profile)?;
+
+        let reasserts = Arc::new(AtomicUsize::new(0));
+        let poller = {
+            let profile = profile.clone();
+            let reasserts = reasserts.clone();
+            spawn_poller(self.config.clone(), reassert_interval, move |ryzen_adj| {
+                if let Ok(writes) = ryzen_adj.apply_profile_delta(&profile) {
+                    reasserts.fetch_add(writes, Ordering::Relaxed);
+                }
+            })?
+        };
+        Ok(ProfileMonitor {
+            reasserts,
+            _poller: poller,
+        })
+    }
+}
+
+/// Watches the limits from a background thread until dropped, see [`RyzenAdj::watch_limits`]
+pub struct LimitWatcher {
+    _poller: Poller,
+}
+
+impl LimitWatcher {
+    /// Stops watching and waits for the background thread to exit, same as dropping it
+    pub fn stop(self) {}
+}
+
+impl RyzenAdj {
+    /// Calls `on_change` with the old and new limits whenever they change, until the returned watcher is dropped
+    ///
+    /// Every `poll` the limits are read with [`RyzenAdj::current_profile`] on a background thread,
+    /// which opens its own ryzenadj instance next to this one like [`RyzenAdj::apply_profile_monitored`],
+    /// with the same options and [`RyzenAdjBuilder::multi_open`]. A change is a limit differing
+    /// by more than [`DELTA_TOLERANCE`] or that stopped or started being reported.
+    /// The first old limits are read on this instance before returning. Errors on the background
+    /// thread are ignored and retried on the next poll.
+    pub fn watch_limits(
+        &self,
+        poll: Duration,
+        mut on_change: impl FnMut(&Profile, &Profile) + Send + 'static,
+    ) -> RyzenAdjResult<LimitWatcher> {
+        let mut last = self.current_profile()?;
+        let poller = spawn_poller(self.config.clone(), poll, move |ryzen_adj| {
+            if let Ok(current) = ryzen_adj.current_profile() {
+                if current.differs(&last) {
+                    on_change(&last, &current);
+                    last = current;
+                }
+            }
+        })?;
+        Ok(LimitWatcher { _poller: poller })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder;
+
+    fn profile() -> Profile {
+        Profile {
+            stapm_limit: Some(15000),
+            fast_limit: Some(25000),
+            ..Profile::default()
+        }
+    }
+
+    #[test]
+    fn clone_is_equal() {
+        let profile = profile();
+        assert_eq!(profile.clone(), profile);
+        assert_ne!(profile, Profile::default());
+    }
+
+    #[test]
+    fn partial_eq_compares_every_field() {
+        let mut other = profile();
+        other.fast_limit = Some(25001);
+        assert_ne!(other, profile());
+        other.fast_limit = Some(25000);
+        other.slow_limit = Some(20000);
+        assert_ne!(other, profile());
+    }
+
+    #[test]
+    fn poller_opens_while_the_parent_is_open() {
+        let _instances = builder::lock_instances();
+        // stands in for the instance spawning the poller, opened without multi_open
+        builder::acquire(false).expect("no other instance is open");
+        let opened = spawn_poller(RyzenAdjBuilder::new(), Duration::from_secs(60), |_| {});
+        builder::release();
+        // without SMU access the open still fails, but never because the parent is open
+        assert!(!matches!(opened, Err(RyzenAdjError::AlreadyOpen)));
+    }
+
+    #[test]
+    fn poller_keeps_the_callers_options_while_the_parent_is_open() {
+        let _instances = builder::lock_instances();
+        builder::acquire(false).expect("no other instance is open");
+        // what watch_limits passes for a parent opened with an SMU lock and an assumed family
+        let config = RyzenAdjBuilder::new()
+            .smu_lock(Duration::from_millis(50))
+            .assume_family(crate::RyzenFamily::Rembrandt);
+        let opened = spawn_poller(config, Duration::from_secs(60), |ryzen_adj| {
+            let _ = ryzen_adj.current_profile();
+        });
+        builder::release();
+        assert!(!matches!(opened, Err(RyzenAdjError::AlreadyOpen)));
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::*;
+
+    #[test]
+    fn missing_version_is_read_as_the_first() {
+        let profile: Profile = serde_json::from_str(r#"{"stapm_limit": 15000}"#).unwrap();
+        assert_eq!(
+            profile,
+            Profile {
+                stapm_limit: Some(15000),
+                ..Profile::default()
+            }
+        );
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        let version = PROFILE_VERSION + 1;
+        let json = format!(r#"{{"version": {version}, "stapm_limit": 15000}}"#);
+        let err = serde_json::from_str::<Profile>(&json).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains(&UnsupportedProfileVersion(version).to_string()),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn profile_round_trips() {
+        let profile = Profile {
+            stapm_limit: Some(15000),
+            fast_limit: Some(25000),
+            slow_time: Some(5),
+            ..Profile::default()
+        };
+        let json = serde_json::to_string(&profile).unwrap();
+        assert!(
+            json.contains(&format!(r#""version":{PROFILE_VERSION}"#)),
+            "{json}"
+        );
+        assert!(
+            !json.contains("slow_limit"),
+            "unset fields are skipped: {json}"
+        );
+        assert_eq!(serde_json::from_str::<Profile>(&json).unwrap(), profile);
+    }
+}
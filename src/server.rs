@@ -0,0 +1,140 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, ErrorKind, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crate::{PowerSnapshot, RyzenAdj, RyzenAdjResult};
+
+/// Clients that do not take a line within this time are disconnected
+const CLIENT_WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+// the same on every unix
+const EBADF: i32 = 9;
+const EFAULT: i32 = 14;
+const EINVAL: i32 = 22;
+
+/// Checks if an `accept` error means the listener itself is broken, other errors like
+/// `ECONNABORTED` or `EMFILE` only affect a single connection attempt
+fn is_listener_fatal(err: &io::Error) -> bool {
+    matches!(err.raw_os_error(), Some(EBADF | EFAULT | EINVAL))
+}
+
+/// Configures an accepted client for blocking writes with [`CLIENT_WRITE_TIMEOUT`]
+fn setup_client(client: &UnixStream) -> io::Result<()> {
+    client.set_nonblocking(false)?;
+    client.set_write_timeout(Some(CLIENT_WRITE_TIMEOUT))
+}
+
+/// Removes the socket file when the server stops, also on errors
+struct SocketFile<'a>(&'a Path);
+
+impl Drop for SocketFile<'_> {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(self.0);
+    }
+}
+
+/// Formats a snapshot as a single line JSON object with a trailing newline
+///
+/// The object has a `timestamp_millis`, every metric keyed by [`Metric::name`](crate::Metric::name)
+/// and a `cores` array of `clk`/`power`/`temp`/`volt` objects, unavailable values are `null`
+fn json_line(snapshot: &PowerSnapshot) -> String {
+    fn value(line: &mut String, value: Option<f32>) {
+        match value {
+            Some(value) if value.is_finite() => write!(line, "{value}").unwrap(),
+            _ => line.push_str("null"),
+        }
+    }
+
+    let mut line = format!("{{\"timestamp_millis\":{}", snapshot.timestamp_millis());
+    for (metric, metric_value) in snapshot.iter() {
+        write!(line, ",\"{}\":", metric.name()).unwrap();
+        value(&mut line, metric_value);
+    }
+    line.push_str(",\"cores\":[");
+    for (i, core) in snapshot.cores.iter().enumerate() {
+        if i > 0 {
+            line.push(',');
+        }
+        line.push_str("{\"clk\":");
+        value(&mut line, core.clk);
+        line.push_str(",\"power\":");
+        value(&mut line, core.power);
+        line.push_str(",\"temp\":");
+        value(&mut line, core.temp);
+        line.push_str(",\"volt\":");
+        value(&mut line, core.volt);
+        line.push('}');
+    }
+    line.push_str("]}\n");
+    line
+}
+
+impl RyzenAdj {
+    /// Binds a Unix socket at `path` and writes a snapshot as one JSON line to every connected client each `interval`
+    ///
+    /// Runs until `stop` is set, which is checked once per interval, then removes the socket file and
+    /// returns. Clients can connect and disconnect at any time, clients that disconnect or do not read
+    /// fast enough are dropped, as are clients failing to connect. Fails if `path` already exists, the
+    /// listener breaks or a snapshot can not be taken. Writing to a disconnected client raises
+    /// `SIGPIPE`, which Rust binaries ignore by default, other hosts have to ignore it as well.
+    pub fn serve_jsonl(
+        &self,
+        path: &Path,
+        interval: Duration,
+        stop: &AtomicBool,
+    ) -> RyzenAdjResult<()> {
+        let listener = UnixListener::bind(path)?;
+        let _socket_file = SocketFile(path);
+        listener.set_nonblocking(true)?;
+
+        let mut clients: Vec<UnixStream> = Vec::new();
+        let mut snapshot = self.snapshot()?;
+        while !stop.load(Ordering::Relaxed) {
+            loop {
+                match listener.accept() {
+                    Ok((client, _)) => {
+                        if setup_client(&client).is_ok() {
+                            clients.push(client);
+                        }
+                    }
+                    Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                    Err(err) if is_listener_fatal(&err) => return Err(err.into()),
+                    // e.g. out of file descriptors, retried on the next interval
+                    Err(_) => break,
+                }
+            }
+
+            let line = json_line(&snapshot);
+            clients.retain_mut(|client| client.write_all(line.as_bytes()).is_ok());
+
+            thread::sleep(interval);
+            self.snapshot_into(&mut snapshot)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_broken_listeners_are_fatal() {
+        for errno in [EBADF, EFAULT, EINVAL] {
+            assert!(is_listener_fatal(&io::Error::from_raw_os_error(errno)));
+        }
+        // EINTR, ENOMEM, ENFILE, EMFILE
+        for errno in [4, 12, 23, 24] {
+            assert!(!is_listener_fatal(&io::Error::from_raw_os_error(errno)));
+        }
+        assert!(!is_listener_fatal(&io::Error::from(
+            ErrorKind::ConnectionAborted
+        )));
+        assert!(!is_listener_fatal(&io::Error::from(ErrorKind::WouldBlock)));
+    }
+}
@@ -0,0 +1,983 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::{NanPolicy, RyzenAdj, RyzenAdjError, RyzenAdjResult, WRITABLE_PARAMS};
+
+/// ryzenadj only reports per core values for up to this many cores
+///
+/// CPUs with more cores only report their first `MAX_CORES` cores, the per core getters report
+/// the others as not available ([`RyzenAdjError::GetNaN`], or `None` in snapshots) instead of
+/// reading past the per core values of the table.
+pub const MAX_CORES: u32 = 8;
+
+/// Maps a NaN reading to `None`, other errors are passed through
+pub(crate) fn available(value: RyzenAdjResult<f32>) -> RyzenAdjResult<Option<f32>> {
+    match value {
+        Ok(value) => Ok(Some(value)),
+        Err(RyzenAdjError::GetNaN) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+macro_rules! metrics {
+    ($($variant:ident => $field:ident, $getter:ident, $doc:literal;)*) => {
+        /// Enumerates the values stored in a [`PowerSnapshot`]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[non_exhaustive]
+        pub enum Metric {
+            $(
+                #[doc = $doc]
+                $variant,
+            )*
+        }
+
+        impl Metric {
+            /// All metrics, in the stable order used by [`PowerSnapshot::iter`]
+            pub const ALL: &'static [Metric] = &[$(Metric::$variant,)*];
+
+            /// Getters read for each metric, in [`Metric::ALL`] order
+            #[cfg(test)]
+            pub(crate) const GETTERS: &'static [&'static str] = &[$(stringify!($getter),)*];
+
+            /// Returns the name of the metric, same as its getter without the `get_` prefix
+            pub const fn name(&self) -> &'static str {
+                match self {
+                    $(Metric::$variant => stringify!($field),)*
+                }
+            }
+        }
+
+        /// All values read out from the table during a single refresh
+        ///
+        /// Values the CPU does not report are `None`. `PartialEq` compares the readings and timestamps
+        /// exactly, use [`PowerSnapshot::approx_eq`] to compare only the readings and allow for sensor noise.
+        ///
+        /// Package C-state (idle) residency is not included, as ryzenadj has no getter for it in
+        /// `lib/ryzenadj.h`. It can only be wrapped once upstream exports a getter reading it from the table.
+        ///
+        /// With the `serde` feature snapshots serialize as a map of the metrics keyed by
+        /// [`Metric::name`], the `cores` and `timestamp` as [`PowerSnapshot::timestamp_millis`]. The
+        /// `instant` is left out, it means nothing outside of this process.
+        #[derive(Debug, Clone, PartialEq)]
+        pub struct PowerSnapshot {
+            $(
+                #[doc = $doc]
+                pub $field: Option<f32>,
+            )*
+            /// Per core values, one entry per core reported by ryzenadj
+            pub cores: Vec<CoreMetrics>,
+            /// Wall clock time the table was refreshed at, see [`PowerSnapshot::timestamp_millis`]
+            pub timestamp: SystemTime,
+            /// Monotonic time the table was refreshed at, for measuring intervals between snapshots
+            pub instant: Instant,
+        }
+
+        impl PowerSnapshot {
+            /// Gets the value of the given metric
+            pub fn get(&self, metric: Metric) -> Option<f32> {
+                match metric {
+                    $(Metric::$variant => self.$field,)*
+                }
+            }
+
+            /// Returns a snapshot without any value, taken now
+            fn unavailable() -> PowerSnapshot {
+                PowerSnapshot {
+                    $($field: None,)*
+                    cores: Vec::new(),
+                    timestamp: SystemTime::now(),
+                    instant: Instant::now(),
+                }
+            }
+
+            pub(crate) fn get_mut(&mut self, metric: Metric) -> &mut Option<f32> {
+                match metric {
+                    $(Metric::$variant => &mut self.$field,)*
+                }
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for PowerSnapshot {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                use serde::ser::SerializeStruct;
+
+                let mut state = serializer.serialize_struct("PowerSnapshot", Metric::ALL.len() + 2)?;
+                $(state.serialize_field(stringify!($field), &self.$field)?;)*
+                state.serialize_field("cores", &self.cores)?;
+                state.serialize_field("timestamp", &self.timestamp_millis())?;
+                state.end()
+            }
+        }
+
+        impl RyzenAdj {
+            /// Gets the value of the given metric from the last refreshed table
+            pub fn get_metric(&self, metric: Metric) -> RyzenAdjResult<f32> {
+                match metric {
+                    $(Metric::$variant => self.$getter(),)*
+                }
+            }
+
+            /// Refreshes the table and reads out all values
+            pub fn snapshot(&self) -> RyzenAdjResult<PowerSnapshot> {
+                self.refresh()?;
+                let timestamp = SystemTime::now();
+                let instant = Instant::now();
+                Ok(PowerSnapshot {
+                    $($field: available(self.$getter())?,)*
+                    cores: self.core_metrics()?,
+                    timestamp,
+                    instant,
+                })
+            }
+
+            /// Refreshes the table and overwrites `buf` with all values, without allocating
+            ///
+            /// The core vector of `buf` is cleared and refilled, reusing its capacity. Start with
+            /// the snapshot returned by a first [`RyzenAdj::snapshot`] call. On error `buf` may be
+            /// partially updated.
+            pub fn snapshot_into(&self, buf: &mut PowerSnapshot) -> RyzenAdjResult<()> {
+                self.refresh()?;
+                buf.timestamp = SystemTime::now();
+                buf.instant = Instant::now();
+                $(buf.$field = available(self.$getter())?;)*
+                self.core_metrics_into(&mut buf.cores)
+            }
+        }
+    };
+}
+
+metrics! {
+    ApuSkinTempLimit => apu_skin_temp_limit, get_apu_skin_temp_limit, "APU skin temperature limit";
+    ApuSkinTempValue => apu_skin_temp_value, get_apu_skin_temp_value, "APU skin temperature value";
+    ApuSlowLimit => apu_slow_limit, get_apu_slow_limit, "APU slow limit";
+    ApuSlowValue => apu_slow_value, get_apu_slow_value, "APU slow value";
+    CclkBusyValue => cclk_busy_value, get_cclk_busy_value, "cclk busy value";
+    CclkSetpoint => cclk_setpoint, get_cclk_setpoint, "cclk setpoint";
+    DgpuSkinTempLimit => dgpu_skin_temp_limit, get_dgpu_skin_temp_limit, "dgpu skin temp limit";
+    DgpuSkinTempValue => dgpu_skin_temp_value, get_dgpu_skin_temp_value, "dgpu skin temp value";
+    FastLimit => fast_limit, get_fast_limit, "fast limit";
+    FastValue => fast_value, get_fast_value, "fast value";
+    Fclk => fclk, get_fclk, "fclk";
+    GfxClk => gfx_clk, get_gfx_clk, "gfx clk";
+    GfxTemp => gfx_temp, get_gfx_temp, "gfx temp";
+    GfxVolt => gfx_volt, get_gfx_volt, "gfx volt";
+    L3Clk => l3_clk, get_l3_clk, "l3 cache clk";
+    L3Logic => l3_logic, get_l3_logic, "l3 logic";
+    L3Temp => l3_temp, get_l3_temp, "l3 temp";
+    L3Vddm => l3_vddm, get_l3_vddm, "l3 vddm";
+    MemClk => mem_clk, get_mem_clk, "mem clk";
+    Psi0Current => psi0_current, get_psi0_current, "psi0 current";
+    Psi0socCurrent => psi0soc_current, get_psi0soc_current, "psi0soc current";
+    SlowLimit => slow_limit, get_slow_limit, "slow limit";
+    SlowTime => slow_time, get_slow_time, "slow time";
+    SlowValue => slow_value, get_slow_value, "slow value";
+    SocPower => soc_power, get_soc_power, "soc power";
+    SocVolt => soc_volt, get_soc_volt, "soc voltage";
+    SocketPower => socket_power, get_socket_power, "socket power";
+    StapmLimit => stapm_limit, get_stapm_limit, "stapm limit";
+    StapmTime => stapm_time, get_stapm_time, "stapm time";
+    StapmValue => stapm_value, get_stapm_value, "stapm value";
+    TctlTemp => tctl_temp, get_tctl_temp, "tctl temp";
+    TctlTempValue => tctl_temp_value, get_tctl_temp_value, "tctl temp value";
+    VrmCurrent => vrm_current, get_vrm_current, "vrm current";
+    VrmCurrentValue => vrm_current_value, get_vrm_current_value, "vrm current value";
+    VrmmaxCurrent => vrmmax_current, get_vrmmax_current, "vrmmax current";
+    VrmmaxCurrentValue => vrmmax_current_value, get_vrmmax_current_value, "vrmmax current value";
+    VrmsocCurrent => vrmsoc_current, get_vrmsoc_current, "vrmsoc current";
+    VrmsocCurrentValue => vrmsoc_current_value, get_vrmsoc_current_value, "vrmsoc current value";
+    VrmsocmaxCurrent => vrmsocmax_current, get_vrmsocmax_current, "vrmsocmax current";
+    VrmsocmaxCurrentValue => vrmsocmax_current_value, get_vrmsocmax_current_value, "vrmsocmax current value";
+}
+
+/// Per core values of a [`PowerSnapshot`]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CoreMetrics {
+    /// core clk
+    pub clk: Option<f32>,
+    /// core power
+    pub power: Option<f32>,
+    /// core temp
+    pub temp: Option<f32>,
+    /// core volt
+    pub volt: Option<f32>,
+}
+
+impl PowerSnapshot {
+    /// Iterates over all metrics and their values
+    ///
+    /// The order is the one of [`Metric::ALL`], which is stable between calls and sorted by metric name
+    pub fn iter(&self) -> impl Iterator<Item = (Metric, Option<f32>)> + '_ {
+        Metric::ALL.iter().map(|&metric| (metric, self.get(metric)))
+    }
+
+    /// Returns [`PowerSnapshot::timestamp`] as milliseconds since the unix epoch, 0 if it is before the epoch
+    pub fn timestamp_millis(&self) -> u64 {
+        self.timestamp
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis() as u64)
+    }
+
+    /// Returns all available values keyed by [`Metric::name`], per core values are not included
+    pub fn to_map(&self) -> HashMap<String, f32> {
+        self.iter()
+            .filter_map(|(metric, value)| value.map(|value| (metric.name().to_string(), value)))
+            .collect()
+    }
+
+    /// Returns the snapshot as one InfluxDB line protocol record, without a trailing newline
+    ///
+    /// Every available metric becomes a float field named by [`Metric::name`], per core values are
+    /// added as `core_clk_0` and so on, like [`RyzenAdj::table_fields`]. Unavailable and non finite
+    /// values are skipped. The record is timestamped with [`PowerSnapshot::timestamp`] in nanoseconds,
+    /// commas, spaces and `=` in `measurement`, `tags` and field keys are escaped. Line protocol
+    /// needs at least one field, if no value is available an empty string is returned.
+    pub fn to_influx_line(&self, measurement: &str, tags: &[(&str, &str)]) -> String {
+        let fields: Vec<String> = self
+            .iter()
+            .filter_map(|(metric, value)| value.map(|value| (metric.name().to_string(), value)))
+            .chain(self.cores.iter().enumerate().flat_map(|(core, metrics)| {
+                [
+                    ("core_clk", metrics.clk),
+                    ("core_power", metrics.power),
+                    ("core_temp", metrics.temp),
+                    ("core_volt", metrics.volt),
+                ]
+                .into_iter()
+                .filter_map(move |(name, value)| {
+                    value.map(|value| (format!("{name}_{core}"), value))
+                })
+            }))
+            .filter(|(_, value)| value.is_finite())
+            .map(|(name, value)| format!("{}={value}", escape_influx_key(&name)))
+            .collect();
+        if fields.is_empty() {
+            return String::new();
+        }
+
+        let mut line = escape_influx(measurement, &[',', ' ']);
+        for (key, value) in tags {
+            line.push(',');
+            line.push_str(&escape_influx_key(key));
+            line.push('=');
+            line.push_str(&escape_influx_key(value));
+        }
+        line.push(' ');
+        line.push_str(&fields.join(","));
+        let nanos = self
+            .timestamp
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_nanos());
+        line.push_str(&format!(" {nanos}"));
+        line
+    }
+
+    /// Returns the values as a flat array for FFI, in [`FLAT_METRICS`] order with NaN for unavailable values
+    ///
+    /// Per core values and timestamps are not included
+    pub fn to_flat_array(&self) -> [f32; FLAT_LEN] {
+        FLAT_METRICS.map(|metric| self.get(metric).unwrap_or(f32::NAN))
+    }
+
+    /// Reverse of [`PowerSnapshot::to_flat_array`], NaN values become `None`
+    ///
+    /// The snapshot has no per core values and is timestamped now
+    pub fn from_flat_array(values: &[f32; FLAT_LEN]) -> PowerSnapshot {
+        let mut snapshot = PowerSnapshot::unavailable();
+        for (metric, &value) in FLAT_METRICS.iter().zip(values) {
+            *snapshot.get_mut(*metric) = (!value.is_nan()).then_some(value);
+        }
+        snapshot
+    }
+
+    /// Returns all available temperatures as `(label, temperature)` pairs, like the label and
+    /// temperature of the components listed by the `sysinfo` crate
+    ///
+    /// Labels are prefixed with `ryzenadj`, e.g. `ryzenadj Tctl` or `ryzenadj Core 0`, temperatures
+    /// are in degree C. Limits are not included.
+    #[cfg(feature = "sysinfo-compat")]
+    pub fn as_components(&self) -> Vec<(String, f32)> {
+        let temperatures = [
+            ("Tctl", self.tctl_temp_value),
+            ("GFX", self.gfx_temp),
+            ("L3", self.l3_temp),
+            ("APU skin", self.apu_skin_temp_value),
+            ("dGPU skin", self.dgpu_skin_temp_value),
+        ];
+        let mut components: Vec<(String, f32)> = temperatures
+            .into_iter()
+            .filter_map(|(label, temp)| temp.map(|temp| (format!("ryzenadj {label}"), temp)))
+            .collect();
+        components.extend(self.cores.iter().enumerate().filter_map(|(core, metrics)| {
+            metrics
+                .temp
+                .map(|temp| (format!("ryzenadj Core {core}"), temp))
+        }));
+        components
+    }
+
+    /// Compares two snapshots, allowing each value to differ by up to `eps`
+    ///
+    /// A value missing in one snapshot only matches a value missing in the other, timestamps are ignored
+    pub fn approx_eq(&self, other: &PowerSnapshot, eps: f32) -> bool {
+        self.iter()
+            .zip(other.iter())
+            .all(|((_, a), (_, b))| approx_eq_opt(a, b, eps))
+            && self.cores.len() == other.cores.len()
+            && self
+                .cores
+                .iter()
+                .zip(&other.cores)
+                .all(|(a, b)| a.approx_eq(b, eps))
+    }
+}
+
+/// Metrics no setter writes, the values reported by sensors and the limits that can not be set
+///
+/// Every other metric is written by the [`WRITABLE_PARAMS`](crate::WRITABLE_PARAMS) entry of the same name
+pub const READONLY_METRICS: &[Metric] = &[
+    Metric::ApuSkinTempValue,
+    Metric::ApuSlowValue,
+    Metric::CclkBusyValue,
+    Metric::CclkSetpoint,
+    Metric::DgpuSkinTempValue,
+    Metric::FastValue,
+    Metric::Fclk,
+    Metric::GfxTemp,
+    Metric::GfxVolt,
+    Metric::L3Clk,
+    Metric::L3Logic,
+    Metric::L3Temp,
+    Metric::L3Vddm,
+    Metric::MemClk,
+    Metric::SlowValue,
+    Metric::SocPower,
+    Metric::SocVolt,
+    Metric::SocketPower,
+    Metric::StapmValue,
+    Metric::TctlTempValue,
+    Metric::VrmCurrentValue,
+    Metric::VrmmaxCurrentValue,
+    Metric::VrmsocCurrentValue,
+    Metric::VrmsocmaxCurrentValue,
+];
+
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+// Every metric is either read only or read back for exactly one writable parameter, and every
+// writable parameter has a metric
+const _: () = {
+    let mut i = 0;
+    while i < Metric::ALL.len() {
+        let name = Metric::ALL[i].name();
+        let mut count = 0;
+        let mut j = 0;
+        while j < READONLY_METRICS.len() {
+            if str_eq(READONLY_METRICS[j].name(), name) {
+                count += 1;
+            }
+            j += 1;
+        }
+        let mut j = 0;
+        while j < WRITABLE_PARAMS.len() {
+            if str_eq(WRITABLE_PARAMS[j].name(), name) {
+                count += 1;
+            }
+            j += 1;
+        }
+        assert!(
+            count == 1,
+            "every metric must be in exactly one of READONLY_METRICS and WRITABLE_PARAMS"
+        );
+        i += 1;
+    }
+    let mut j = 0;
+    while j < WRITABLE_PARAMS.len() {
+        let mut found = false;
+        let mut i = 0;
+        while i < Metric::ALL.len() {
+            found |= str_eq(Metric::ALL[i].name(), WRITABLE_PARAMS[j].name());
+            i += 1;
+        }
+        assert!(found, "every WRITABLE_PARAMS entry must have a metric");
+        j += 1;
+    }
+};
+
+/// Number of values in [`PowerSnapshot::to_flat_array`]
+pub const FLAT_LEN: usize = 40;
+
+/// Metric stored at each index of [`PowerSnapshot::to_flat_array`]
+///
+/// The mapping is stable: indices are never reordered or reused, new metrics are appended
+pub const FLAT_METRICS: [Metric; FLAT_LEN] = [
+    Metric::ApuSkinTempLimit,
+    Metric::ApuSkinTempValue,
+    Metric::ApuSlowLimit,
+    Metric::ApuSlowValue,
+    Metric::CclkBusyValue,
+    Metric::CclkSetpoint,
+    Metric::DgpuSkinTempLimit,
+    Metric::DgpuSkinTempValue,
+    Metric::FastLimit,
+    Metric::FastValue,
+    Metric::Fclk,
+    Metric::GfxClk,
+    Metric::GfxTemp,
+    Metric::GfxVolt,
+    Metric::L3Clk,
+    Metric::L3Logic,
+    Metric::L3Temp,
+    Metric::L3Vddm,
+    Metric::MemClk,
+    Metric::Psi0Current,
+    Metric::Psi0socCurrent,
+    Metric::SlowLimit,
+    Metric::SlowTime,
+    Metric::SlowValue,
+    Metric::SocPower,
+    Metric::SocVolt,
+    Metric::SocketPower,
+    Metric::StapmLimit,
+    Metric::StapmTime,
+    Metric::StapmValue,
+    Metric::TctlTemp,
+    Metric::TctlTempValue,
+    Metric::VrmCurrent,
+    Metric::VrmCurrentValue,
+    Metric::VrmmaxCurrent,
+    Metric::VrmmaxCurrentValue,
+    Metric::VrmsocCurrent,
+    Metric::VrmsocCurrentValue,
+    Metric::VrmsocmaxCurrent,
+    Metric::VrmsocmaxCurrentValue,
+];
+
+// Every metric has to be in FLAT_METRICS exactly once
+const _: () = {
+    assert!(
+        FLAT_LEN == Metric::ALL.len(),
+        "FLAT_METRICS must list every metric"
+    );
+    let mut seen = [false; FLAT_LEN];
+    let mut i = 0;
+    while i < FLAT_LEN {
+        let metric = FLAT_METRICS[i] as usize;
+        assert!(!seen[metric], "FLAT_METRICS must list every metric once");
+        seen[metric] = true;
+        i += 1;
+    }
+};
+
+impl Metric {
+    /// Returns the unit of the values returned by the getter, empty for the unitless cclk values
+    pub fn unit(&self) -> &'static str {
+        use Metric::*;
+
+        match self {
+            ApuSkinTempLimit | ApuSkinTempValue | DgpuSkinTempLimit | DgpuSkinTempValue
+            | GfxTemp | L3Temp | TctlTemp | TctlTempValue => "°C",
+            ApuSlowLimit | ApuSlowValue | FastLimit | FastValue | L3Logic | L3Vddm | SlowLimit
+            | SlowValue | SocPower | SocketPower | StapmLimit | StapmValue => "W",
+            Fclk | GfxClk | L3Clk | MemClk => "MHz",
+            GfxVolt | SocVolt => "V",
+            Psi0Current
+            | Psi0socCurrent
+            | VrmCurrent
+            | VrmCurrentValue
+            | VrmmaxCurrent
+            | VrmmaxCurrentValue
+            | VrmsocCurrent
+            | VrmsocCurrentValue
+            | VrmsocmaxCurrent
+            | VrmsocmaxCurrentValue => "A",
+            SlowTime | StapmTime => "s",
+            CclkBusyValue | CclkSetpoint => "",
+        }
+    }
+}
+
+/// Formats a value of the given metric followed by its [`Metric::unit`], e.g. `15.5 W`
+pub fn format_with_units(metric: Metric, value: f32) -> String {
+    with_unit(value, metric.unit())
+}
+
+fn with_unit(value: f32, unit: &str) -> String {
+    if unit.is_empty() {
+        value.to_string()
+    } else {
+        format!("{value} {unit}")
+    }
+}
+
+/// One `name: value unit` line per metric in [`Metric::ALL`] order, `n/a` for values that are not available,
+/// followed by `core_clk`, `core_power`, `core_temp` and `core_volt` lines listing the values of all cores
+impl fmt::Display for PowerSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn value(value: Option<f32>, unit: &str) -> String {
+            value.map_or_else(|| "n/a".to_string(), |value| with_unit(value, unit))
+        }
+
+        for (metric, metric_value) in self.iter() {
+            writeln!(
+                f,
+                "{}: {}",
+                metric.name(),
+                value(metric_value, metric.unit())
+            )?;
+        }
+        let cores = |get: fn(&CoreMetrics) -> Option<f32>, unit: &str| {
+            let values: Vec<String> = self
+                .cores
+                .iter()
+                .map(|core| value(get(core), unit))
+                .collect();
+            values.join(", ")
+        };
+        writeln!(f, "core_clk: [{}]", cores(|core| core.clk, "MHz"))?;
+        writeln!(f, "core_power: [{}]", cores(|core| core.power, "W"))?;
+        writeln!(f, "core_temp: [{}]", cores(|core| core.temp, "°C"))?;
+        write!(f, "core_volt: [{}]", cores(|core| core.volt, "V"))?;
+        Ok(())
+    }
+}
+
+/// Escapes the `special` characters of a line protocol element with a backslash
+fn escape_influx(value: &str, special: &[char]) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if special.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Escapes a tag key, tag value or field key
+fn escape_influx_key(key: &str) -> String {
+    escape_influx(key, &[',', '=', ' '])
+}
+
+pub(crate) fn approx_eq_opt(a: Option<f32>, b: Option<f32>, eps: f32) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => (a - b).abs() <= eps,
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+impl RyzenAdj {
+    /// Gets the value of the given metric, `Ok(None)` if the CPU does not report it
+    ///
+    /// Unlike [`RyzenAdj::get_metric`] a NaN reading is not an error, so `Err` is only returned
+    /// when the table itself is broken (e.g. [`RyzenAdjError::InitTableError`]). With
+    /// [`NanPolicy::Error`] NaN readings are returned as [`RyzenAdjError::GetNaN`] instead.
+    pub fn get_opt(&self, metric: Metric) -> RyzenAdjResult<Option<f32>> {
+        match self.nan_policy.get() {
+            NanPolicy::Error => self.get_metric(metric).map(Some),
+            NanPolicy::Zero | NanPolicy::None => available(self.get_metric(metric)),
+        }
+    }
+
+    /// Refreshes the table and returns every metric the CPU does not report, in [`Metric::ALL`] order
+    pub fn unavailable_metrics(&self) -> RyzenAdjResult<Vec<Metric>> {
+        self.refresh()?;
+        let mut unavailable = Vec::new();
+        for &metric in Metric::ALL {
+            if available(self.get_metric(metric))?.is_none() {
+                unavailable.push(metric);
+            }
+        }
+        Ok(unavailable)
+    }
+
+    /// Refreshes the table and returns every value reported by a getter, keyed by getter name without the `get_` prefix
+    ///
+    /// Per core values are keyed with the core appended, e.g. `core_clk_0`. Meant for exploring tables
+    /// and diffing them across BIOS versions, the set of keys is not stable, use [`RyzenAdj::snapshot`]
+    /// for that. Values the CPU does not report are left out. Table offsets no getter reads from are
+    /// only available through [`RyzenAdj::raw_table_bytes`].
+    pub fn table_fields(&self) -> RyzenAdjResult<BTreeMap<String, f32>> {
+        self.refresh()?;
+        let mut fields = BTreeMap::new();
+        for &metric in Metric::ALL {
+            if let Some(value) = available(self.get_metric(metric))? {
+                fields.insert(metric.name().to_string(), value);
+            }
+        }
+        for core in 0..MAX_CORES {
+            let values = [
+                ("core_clk", self.get_core_clk(core)),
+                ("core_power", self.get_core_power(core)),
+                ("core_temp", self.get_core_temp(core)),
+                ("core_volt", self.get_core_volt(core)),
+            ];
+            for (name, value) in values {
+                if let Some(value) = available(value)? {
+                    fields.insert(format!("{name}_{core}"), value);
+                }
+            }
+        }
+        Ok(fields)
+    }
+
+    /// Reads the per core values, trailing cores without any value are left out
+    fn core_metrics(&self) -> RyzenAdjResult<Vec<CoreMetrics>> {
+        let mut cores = Vec::with_capacity(MAX_CORES as usize);
+        self.core_metrics_into(&mut cores)?;
+        Ok(cores)
+    }
+
+    /// Clears `cores` and refills it like [`RyzenAdj::core_metrics`]
+    fn core_metrics_into(&self, cores: &mut Vec<CoreMetrics>) -> RyzenAdjResult<()> {
+        cores.clear();
+        for core in 0..MAX_CORES {
+            cores.push(CoreMetrics {
+                clk: available(self.get_core_clk(core))?,
+                power: available(self.get_core_power(core))?,
+                temp: available(self.get_core_temp(core))?,
+                volt: available(self.get_core_volt(core))?,
+            });
+        }
+        while cores.last().is_some_and(CoreMetrics::is_empty) {
+            cores.pop();
+        }
+        Ok(())
+    }
+}
+
+impl CoreMetrics {
+    /// Compares two cores, allowing each value to differ by up to `eps`
+    pub fn approx_eq(&self, other: &CoreMetrics, eps: f32) -> bool {
+        approx_eq_opt(self.clk, other.clk, eps)
+            && approx_eq_opt(self.power, other.power, eps)
+            && approx_eq_opt(self.temp, other.temp, eps)
+            && approx_eq_opt(self.volt, other.volt, eps)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.clk.is_none() && self.power.is_none() && self.temp.is_none() && self.volt.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn core_with_clk(clk: Option<f32>) -> CoreMetrics {
+        CoreMetrics {
+            clk,
+            power: Some(1.5),
+            temp: None,
+            volt: Some(1.1),
+        }
+    }
+
+    /// Splits `value` at every `separator` not escaped with a backslash, keeping the escapes
+    fn split_unescaped(value: &str, separator: char) -> Vec<String> {
+        let mut parts = vec![String::new()];
+        let mut chars = value.chars();
+        while let Some(c) = chars.next() {
+            let part = parts.last_mut().unwrap();
+            match c {
+                '\\' => {
+                    part.push(c);
+                    part.extend(chars.next());
+                }
+                c if c == separator => parts.push(String::new()),
+                c => part.push(c),
+            }
+        }
+        parts
+    }
+
+    fn unescape(value: &str) -> String {
+        let mut unescaped = String::new();
+        let mut chars = value.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => unescaped.extend(chars.next()),
+                c => unescaped.push(c),
+            }
+        }
+        unescaped
+    }
+
+    fn key_value(pair: &str) -> (String, String) {
+        match &split_unescaped(pair, '=')[..] {
+            [key, value] => (unescape(key), unescape(value)),
+            _ => panic!("not a key value pair: {pair}"),
+        }
+    }
+
+    type InfluxRecord = (String, Vec<(String, String)>, Vec<(String, f32)>, u128);
+
+    /// Parses a line protocol record into its measurement, tags, fields and timestamp
+    fn parse_influx_line(line: &str) -> InfluxRecord {
+        let sections = split_unescaped(line, ' ');
+        let [series, fields, timestamp] = &sections[..] else {
+            panic!("not a record: {line}");
+        };
+        let series = split_unescaped(series, ',');
+        let tags = series[1..].iter().map(|tag| key_value(tag)).collect();
+        let fields = split_unescaped(fields, ',')
+            .iter()
+            .map(|field| {
+                let (key, value) = key_value(field);
+                (key, value.parse().unwrap())
+            })
+            .collect();
+        (
+            unescape(&series[0]),
+            tags,
+            fields,
+            timestamp.parse().unwrap(),
+        )
+    }
+
+    #[test]
+    fn influx_line_parses_back() {
+        let mut snapshot = PowerSnapshot::unavailable();
+        snapshot.timestamp = UNIX_EPOCH + Duration::new(1_700_000_000, 123);
+        snapshot.stapm_limit = Some(15.0);
+        snapshot.tctl_temp_value = Some(65.5);
+        snapshot.fast_value = Some(f32::NAN);
+        snapshot.cores = vec![CoreMetrics {
+            clk: Some(3000.0),
+            power: None,
+            temp: Some(70.25),
+            volt: None,
+        }];
+
+        let line = snapshot.to_influx_line("ryzen adj,cpu", &[("host name", "a=b,c")]);
+        let (measurement, tags, fields, timestamp) = parse_influx_line(&line);
+        assert_eq!(measurement, "ryzen adj,cpu");
+        assert_eq!(tags, [("host name".to_string(), "a=b,c".to_string())]);
+        assert_eq!(
+            fields,
+            [
+                ("stapm_limit".to_string(), 15.0),
+                ("tctl_temp_value".to_string(), 65.5),
+                ("core_clk_0".to_string(), 3000.0),
+                ("core_temp_0".to_string(), 70.25),
+            ]
+        );
+        assert_eq!(timestamp, 1_700_000_000_000_000_123);
+        assert!(line.ends_with(" 1700000000000000123"));
+    }
+
+    #[test]
+    fn influx_keys_are_escaped() {
+        assert_eq!(escape_influx_key("core clk,0=a"), "core\\ clk\\,0\\=a");
+        assert_eq!(
+            key_value(&format!("{}=1", escape_influx_key("a=b c"))).0,
+            "a=b c"
+        );
+        assert_eq!(escape_influx("a=b c,d", &[',', ' ']), "a=b\\ c\\,d");
+    }
+
+    #[test]
+    fn influx_line_without_values_is_empty() {
+        let mut snapshot = PowerSnapshot::unavailable();
+        snapshot.socket_power = Some(f32::INFINITY);
+        assert_eq!(snapshot.to_influx_line("ryzenadj", &[]), "");
+    }
+
+    #[test]
+    fn clone_is_equal() {
+        let mut snapshot = PowerSnapshot::unavailable();
+        snapshot.fast_limit = Some(25.0);
+        snapshot.cores = vec![core_with_clk(Some(3200.0))];
+        let clone = snapshot.clone();
+        assert_eq!(clone, snapshot);
+        assert!(clone.approx_eq(&snapshot, 0.0));
+    }
+
+    #[test]
+    fn partial_eq_is_exact_unlike_approx_eq() {
+        let mut a = PowerSnapshot::unavailable();
+        let mut b = a.clone();
+        a.socket_power = Some(15.0);
+        b.socket_power = Some(15.001);
+        assert_ne!(a, b);
+        assert!(a.approx_eq(&b, 0.01));
+
+        // timestamps are compared by PartialEq only
+        let mut c = a.clone();
+        c.timestamp += Duration::from_millis(1);
+        assert_ne!(a, c);
+        assert!(a.approx_eq(&c, 0.0));
+
+        // NaN never equals itself
+        a.socket_power = Some(f32::NAN);
+        assert_ne!(a, a.clone());
+    }
+
+    #[test]
+    fn core_metrics_partial_eq_and_approx_eq() {
+        let core = core_with_clk(Some(3000.0));
+        assert_eq!(core.clone(), core);
+        assert_ne!(core, core_with_clk(Some(3000.5)));
+        assert!(core.approx_eq(&core_with_clk(Some(3000.5)), 1.0));
+        assert!(!core.approx_eq(&core_with_clk(None), 1.0));
+    }
+
+    #[test]
+    fn format_with_units_per_unit_category() {
+        let cases = [
+            (Metric::TctlTempValue, 65.5, "65.5 °C"),
+            (Metric::SocketPower, 15.25, "15.25 W"),
+            (Metric::Fclk, 1600.0, "1600 MHz"),
+            (Metric::GfxVolt, 1.1, "1.1 V"),
+            (Metric::VrmCurrent, 90.0, "90 A"),
+            (Metric::StapmTime, 200.0, "200 s"),
+            (Metric::CclkBusyValue, 42.0, "42"),
+        ];
+        for (metric, value, formatted) in cases {
+            assert_eq!(format_with_units(metric, value), formatted);
+        }
+    }
+
+    #[test]
+    fn display_uses_units_and_marks_unavailable_values() {
+        let mut snapshot = PowerSnapshot::unavailable();
+        snapshot.stapm_limit = Some(15.0);
+        snapshot.cores = vec![core_with_clk(Some(3000.0)), core_with_clk(None)];
+        let text = snapshot.to_string();
+        assert!(text.contains("\nstapm_limit: 15 W\n"), "{text}");
+        assert!(text.contains("\nfast_limit: n/a\n"), "{text}");
+        assert!(text.contains("core_clk: [3000 MHz, n/a]"), "{text}");
+        assert!(text.ends_with("core_volt: [1.1 V, 1.1 V]"), "{text}");
+    }
+
+    #[test]
+    fn flat_array_indices_line_up_with_metrics() {
+        let mut snapshot = PowerSnapshot::unavailable();
+        for (i, metric) in Metric::ALL.iter().enumerate() {
+            *snapshot.get_mut(*metric) = Some(i as f32 + 0.5);
+        }
+        let flat = snapshot.to_flat_array();
+        for (i, metric) in FLAT_METRICS.iter().enumerate() {
+            assert_eq!(
+                Some(flat[i]),
+                snapshot.get(*metric),
+                "index {i} is {}",
+                metric.name()
+            );
+        }
+        // the mapping is stable, spot check both ends
+        assert_eq!(FLAT_METRICS[0].name(), "apu_skin_temp_limit");
+        assert_eq!(FLAT_METRICS[FLAT_LEN - 1].name(), "vrmsocmax_current_value");
+        assert_eq!(flat[0], snapshot.apu_skin_temp_limit.unwrap());
+        assert_eq!(
+            flat[FLAT_LEN - 1],
+            snapshot.vrmsocmax_current_value.unwrap()
+        );
+    }
+
+    #[test]
+    fn flat_array_round_trips_with_nan_for_unavailable() {
+        let mut snapshot = PowerSnapshot::unavailable();
+        snapshot.stapm_limit = Some(15.0);
+        snapshot.gfx_volt = Some(0.9);
+        let flat = snapshot.to_flat_array();
+        assert_eq!(
+            flat.iter().filter(|value| value.is_nan()).count(),
+            FLAT_LEN - 2
+        );
+
+        let back = PowerSnapshot::from_flat_array(&flat);
+        assert!(back.approx_eq(&snapshot, 0.0));
+        assert_eq!(back.fast_limit, None);
+        assert_eq!(back.gfx_volt, Some(0.9));
+    }
+
+    #[test]
+    fn approx_eq_opt_mixed_none_and_some() {
+        assert!(approx_eq_opt(None, None, 0.0));
+        assert!(!approx_eq_opt(None, Some(1.0), 10.0));
+        assert!(!approx_eq_opt(Some(1.0), None, 10.0));
+        assert!(approx_eq_opt(Some(1.0), Some(1.4), 0.5));
+        assert!(approx_eq_opt(Some(1.0), Some(1.5), 0.5));
+        assert!(!approx_eq_opt(Some(1.0), Some(1.6), 0.5));
+    }
+
+    #[test]
+    fn snapshot_approx_eq_compares_metrics_within_eps() {
+        let mut a = PowerSnapshot::unavailable();
+        let mut b = PowerSnapshot::unavailable();
+        assert!(a.approx_eq(&b, 0.0));
+
+        a.stapm_limit = Some(15.0);
+        assert!(!a.approx_eq(&b, 1.0));
+        assert!(!b.approx_eq(&a, 1.0));
+
+        b.stapm_limit = Some(15.2);
+        assert!(a.approx_eq(&b, 0.5));
+        assert!(!a.approx_eq(&b, 0.1));
+    }
+
+    #[test]
+    fn snapshot_approx_eq_compares_cores_and_ignores_timestamps() {
+        let mut a = PowerSnapshot::unavailable();
+        let mut b = PowerSnapshot::unavailable();
+        b.timestamp = a.timestamp + Duration::from_secs(5);
+        a.cores = vec![core_with_clk(Some(3000.0))];
+        b.cores = vec![core_with_clk(Some(3000.4))];
+        assert!(a.approx_eq(&b, 0.5));
+        assert!(!a.approx_eq(&b, 0.1));
+
+        b.cores[0].clk = None;
+        assert!(!a.approx_eq(&b, 1000.0));
+
+        b.cores = vec![core_with_clk(Some(3000.0)), core_with_clk(Some(3000.0))];
+        assert!(!a.approx_eq(&b, 1.0));
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn snapshot_serializes_timestamp_as_millis_without_instant() {
+        let mut snapshot = PowerSnapshot::unavailable();
+        snapshot.stapm_limit = Some(15.0);
+        snapshot.timestamp = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+        snapshot.cores = vec![CoreMetrics {
+            clk: Some(3000.0),
+            power: None,
+            temp: None,
+            volt: None,
+        }];
+
+        let value = serde_json::to_value(&snapshot).unwrap();
+        assert_eq!(value["timestamp"], 1_700_000_000_123u64);
+        assert_eq!(value["stapm_limit"], 15.0);
+        assert!(value["fast_limit"].is_null());
+        assert_eq!(value["cores"][0]["clk"], 3000.0);
+        assert!(value.get("instant").is_none());
+        // every metric plus the cores and the timestamp
+        assert_eq!(value.as_object().unwrap().len(), Metric::ALL.len() + 2);
+    }
+}
@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+
+use crate::{Metric, PowerSnapshot};
+
+/// Minimum, maximum and average of a single metric, see [`StatsReport`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MetricStats {
+    /// lowest value seen
+    pub min: f32,
+    /// highest value seen
+    pub max: f32,
+    /// average over all samples
+    pub avg: f32,
+    /// number of samples where the metric was available
+    pub samples: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Running {
+    min: f32,
+    max: f32,
+    sum: f64,
+    samples: usize,
+}
+
+/// Collects min/max/avg per metric over a series of snapshots
+///
+/// Metrics that are `None` in a snapshot are ignored for that snapshot
+#[derive(Debug, Clone, Default)]
+pub struct StatsAccumulator {
+    metrics: HashMap<Metric, Running>,
+    snapshots: usize,
+}
+
+impl StatsAccumulator {
+    /// Returns an empty accumulator
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds all available values of the snapshot
+    pub fn update(&mut self, snapshot: &PowerSnapshot) {
+        self.snapshots += 1;
+        for (metric, value) in snapshot.iter() {
+            let Some(value) = value else { continue };
+            let running = self.metrics.entry(metric).or_insert(Running {
+                min: value,
+                max: value,
+                sum: 0.0,
+                samples: 0,
+            });
+            running.min = running.min.min(value);
+            running.max = running.max.max(value);
+            running.sum += value as f64;
+            running.samples += 1;
+        }
+    }
+
+    /// Returns the collected stats
+    pub fn finish(self) -> StatsReport {
+        let metrics = Metric::ALL
+            .iter()
+            .filter_map(|metric| {
+                self.metrics.get(metric).map(|running| {
+                    let stats = MetricStats {
+                        min: running.min,
+                        max: running.max,
+                        avg: (running.sum / running.samples as f64) as f32,
+                        samples: running.samples,
+                    };
+                    (*metric, stats)
+                })
+            })
+            .collect();
+
+        StatsReport {
+            metrics,
+            snapshots: self.snapshots,
+        }
+    }
+}
+
+/// Stats returned by [`StatsAccumulator::finish`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatsReport {
+    metrics: Vec<(Metric, MetricStats)>,
+    snapshots: usize,
+}
+
+impl StatsReport {
+    /// Gets the stats of the given metric, `None` if it was never available
+    pub fn get(&self, metric: Metric) -> Option<MetricStats> {
+        self.metrics
+            .iter()
+            .find(|(m, _)| *m == metric)
+            .map(|(_, stats)| *stats)
+    }
+
+    /// Iterates over all metrics that had at least one value, in [`Metric::ALL`] order
+    pub fn iter(&self) -> impl Iterator<Item = (Metric, MetricStats)> + '_ {
+        self.metrics.iter().copied()
+    }
+
+    /// Number of snapshots fed into the accumulator
+    pub fn snapshots(&self) -> usize {
+        self.snapshots
+    }
+}
+
+/// Exponentially weighted moving average over snapshots, to smooth out noisy readings
+///
+/// Each value of the returned snapshots is the average of that value over all snapshots seen so far,
+/// weighted by `alpha` for the newest one. `None` inputs are skipped and keep the previous average.
+/// Wraps any iterator of snapshots, or feed snapshots by hand through [`SmoothedStream::smooth`].
+#[derive(Debug, Clone)]
+pub struct SmoothedStream<I> {
+    inner: I,
+    alpha: f32,
+    metrics: HashMap<Metric, f32>,
+    cores: Vec<[Option<f32>; 4]>,
+}
+
+impl<I> SmoothedStream<I> {
+    /// Wraps `inner`, `alpha` is the weight of the newest snapshot
+    ///
+    /// # Panics
+    /// - if `alpha` is not in 0.0 (exclusive) to 1.0 (inclusive)
+    pub fn new(inner: I, alpha: f32) -> Self {
+        assert!(
+            alpha > 0.0 && alpha <= 1.0,
+            "SmoothedStream alpha must be in (0, 1]"
+        );
+        Self {
+            inner,
+            alpha,
+            metrics: HashMap::new(),
+            cores: Vec::new(),
+        }
+    }
+
+    /// Adds a snapshot to the averages and returns it with all values replaced by their average
+    pub fn smooth(&mut self, mut snapshot: PowerSnapshot) -> PowerSnapshot {
+        for &metric in Metric::ALL {
+            let value = snapshot.get_mut(metric);
+            *value = Self::update(self.alpha, self.metrics.get(&metric).copied(), *value);
+            if let Some(average) = *value {
+                self.metrics.insert(metric, average);
+            }
+        }
+
+        if self.cores.len() < snapshot.cores.len() {
+            self.cores.resize(snapshot.cores.len(), [None; 4]);
+        }
+        for (core, averages) in snapshot.cores.iter_mut().zip(&mut self.cores) {
+            let values = [
+                &mut core.clk,
+                &mut core.power,
+                &mut core.temp,
+                &mut core.volt,
+            ];
+            for (value, average) in values.into_iter().zip(averages.iter_mut()) {
+                *value = Self::update(self.alpha, *average, *value);
+                *average = *value;
+            }
+        }
+        snapshot
+    }
+
+    fn update(alpha: f32, average: Option<f32>, value: Option<f32>) -> Option<f32> {
+        match (average, value) {
+            (Some(average), Some(value)) => Some(average + alpha * (value - average)),
+            (average, None) => average,
+            (None, value) => value,
+        }
+    }
+
+    /// Returns the wrapped iterator
+    pub fn into_inner(self) -> I {
+        self.inner
+    }
+}
+
+impl<I: Iterator<Item = PowerSnapshot>> Iterator for SmoothedStream<I> {
+    type Item = PowerSnapshot;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let snapshot = self.inner.next()?;
+        Some(self.smooth(snapshot))
+    }
+}
@@ -0,0 +1,548 @@
+use std::time::Duration;
+
+use crate::snapshot::available;
+use crate::{RyzenAdj, RyzenAdjError, RyzenAdjResult, MAX_CORES};
+
+/// fclk:mclk ratios closer to 1 than this are considered 1:1 coupled
+pub const COUPLED_RATIO_TOLERANCE: f32 = 0.02;
+
+/// Memory related clocks, returned by [`RyzenAdj::memory_summary`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemorySummary {
+    /// mem clk
+    pub mem_clk: Option<f32>,
+    /// fclk
+    pub fclk: Option<f32>,
+    /// fclk divided by mem clk, `None` if any of them is not reported
+    pub fclk_mclk_ratio: Option<f32>,
+    /// fclk and mem clk look 1:1 coupled, within [`COUPLED_RATIO_TOLERANCE`]
+    pub coupled: bool,
+}
+
+impl RyzenAdj {
+    /// Gets mem clk and fclk together with their coupling ratio
+    pub fn memory_summary(&self) -> RyzenAdjResult<MemorySummary> {
+        let mem_clk = available(self.get_mem_clk())?;
+        let fclk = available(self.get_fclk())?;
+        let fclk_mclk_ratio = match (fclk, mem_clk) {
+            (Some(fclk), Some(mem_clk)) if mem_clk != 0.0 => Some(fclk / mem_clk),
+            _ => None,
+        };
+        let coupled =
+            fclk_mclk_ratio.is_some_and(|ratio| (ratio - 1.0).abs() <= COUPLED_RATIO_TOLERANCE);
+
+        Ok(MemorySummary {
+            mem_clk,
+            fclk,
+            fclk_mclk_ratio,
+            coupled,
+        })
+    }
+
+    /// Returns if fclk runs 1:1 with the memory clock (uclk), within [`COUPLED_RATIO_TOLERANCE`]
+    ///
+    /// Unlike [`MemorySummary::coupled`] this returns [`RyzenAdjError::GetNaN`] instead of `false` if
+    /// fclk or mem clk is not reported, see [`RyzenAdj::memory_summary`] for the raw ratio
+    pub fn fclk_uclk_coupled(&self) -> RyzenAdjResult<bool> {
+        let summary = self.memory_summary()?;
+        summary
+            .fclk_mclk_ratio
+            .map(|_| summary.coupled)
+            .ok_or(RyzenAdjError::GetNaN)
+    }
+}
+
+impl RyzenAdj {
+    /// Gets core clk divided by core power, in MHz per watt
+    ///
+    /// Returns [`RyzenAdjError::GetNaN`] if the core reports no power draw, as the ratio would not be finite
+    pub fn core_efficiency(&self, core: u32) -> RyzenAdjResult<f32> {
+        let clk = self.get_core_clk(core)?;
+        let power = self.get_core_power(core)?;
+        if power > 0.0 {
+            Ok(clk / power)
+        } else {
+            Err(RyzenAdjError::GetNaN)
+        }
+    }
+
+    /// Gets the average of [`RyzenAdj::core_efficiency`] over all cores reporting clk and power
+    pub fn average_core_efficiency(&self) -> RyzenAdjResult<f32> {
+        let mut sum = 0.0;
+        let mut cores = 0;
+        for core in 0..MAX_CORES {
+            match self.core_efficiency(core) {
+                Ok(efficiency) => {
+                    sum += efficiency;
+                    cores += 1;
+                }
+                Err(RyzenAdjError::GetNaN) => {}
+                Err(err) => return Err(err),
+            }
+        }
+        if cores > 0 {
+            Ok(sum / cores as f32)
+        } else {
+            Err(RyzenAdjError::GetNaN)
+        }
+    }
+}
+
+/// Limit, current value and time constant of one PPT window, in W and s
+#[derive(Debug, Clone, PartialEq)]
+pub struct PptWindow {
+    /// the configured limit
+    pub limit: Option<f32>,
+    /// the current, averaged over the time constant, value
+    pub value: Option<f32>,
+    /// the time constant the value is averaged over, always `None` for the fast PPT window
+    pub time: Option<Duration>,
+}
+
+/// All PPT windows of the power algorithm, returned by [`RyzenAdj::ppt_state`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PptState {
+    /// STAPM, the long term average
+    pub stapm: PptWindow,
+    /// slow PPT, the short term average
+    pub slow: PptWindow,
+    /// fast PPT, the instantaneous limit
+    pub fast: PptWindow,
+}
+
+impl RyzenAdj {
+    /// Gets the limits, values and time constants of all PPT windows, values not reported by the CPU are `None`
+    pub fn ppt_state(&self) -> RyzenAdjResult<PptState> {
+        let time =
+            |value: Option<f32>| value.and_then(|secs| Duration::try_from_secs_f32(secs).ok());
+        Ok(PptState {
+            stapm: PptWindow {
+                limit: available(self.get_stapm_limit())?,
+                value: available(self.get_stapm_value())?,
+                time: time(available(self.get_stapm_time())?),
+            },
+            slow: PptWindow {
+                limit: available(self.get_slow_limit())?,
+                value: available(self.get_slow_value())?,
+                time: time(available(self.get_slow_time())?),
+            },
+            fast: PptWindow {
+                limit: available(self.get_fast_limit())?,
+                value: available(self.get_fast_value())?,
+                time: None,
+            },
+        })
+    }
+}
+
+/// iGPU values, returned by [`RyzenAdj::gpu_state`]
+///
+/// ryzenadj can not read back the min/max gfxclk bounds set with `set_min_gfxclk_freq`/`set_max_gfxclk_freq`,
+/// so they are not included
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpuState {
+    /// gfx clk (MHz)
+    pub gfx_clk: Option<f32>,
+    /// gfx volt (V)
+    pub gfx_volt: Option<f32>,
+    /// gfx temp (degree C)
+    pub gfx_temp: Option<f32>,
+}
+
+impl RyzenAdj {
+    /// Gets all iGPU values at once, values not reported by the CPU are `None`
+    pub fn gpu_state(&self) -> RyzenAdjResult<GpuState> {
+        Ok(GpuState {
+            gfx_clk: available(self.get_gfx_clk())?,
+            gfx_volt: available(self.get_gfx_volt())?,
+            gfx_temp: available(self.get_gfx_temp())?,
+        })
+    }
+}
+
+/// Everything that is not a core: SoC, iGPU, L3 and memory values, returned by [`RyzenAdj::uncore`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct UncoreSummary {
+    /// soc power (W)
+    pub soc_power: Option<f32>,
+    /// soc voltage (V)
+    pub soc_volt: Option<f32>,
+    /// gfx clk (MHz)
+    pub gfx_clk: Option<f32>,
+    /// gfx volt (V)
+    pub gfx_volt: Option<f32>,
+    /// gfx temp (degree C)
+    pub gfx_temp: Option<f32>,
+    /// l3 cache clk (MHz)
+    pub l3_clk: Option<f32>,
+    /// l3 logic (W)
+    pub l3_logic: Option<f32>,
+    /// l3 temp (degree C)
+    pub l3_temp: Option<f32>,
+    /// l3 vddm (W)
+    pub l3_vddm: Option<f32>,
+    /// mem clk (MHz)
+    pub mem_clk: Option<f32>,
+    /// fclk (MHz)
+    pub fclk: Option<f32>,
+}
+
+impl RyzenAdj {
+    /// Gets all SoC, iGPU, L3 and memory values at once, values not reported by the CPU are `None`
+    pub fn uncore(&self) -> RyzenAdjResult<UncoreSummary> {
+        Ok(UncoreSummary {
+            soc_power: available(self.get_soc_power())?,
+            soc_volt: available(self.get_soc_volt())?,
+            gfx_clk: available(self.get_gfx_clk())?,
+            gfx_volt: available(self.get_gfx_volt())?,
+            gfx_temp: available(self.get_gfx_temp())?,
+            l3_clk: available(self.get_l3_clk())?,
+            l3_logic: available(self.get_l3_logic())?,
+            l3_temp: available(self.get_l3_temp())?,
+            l3_vddm: available(self.get_l3_vddm())?,
+            mem_clk: available(self.get_mem_clk())?,
+            fclk: available(self.get_fclk())?,
+        })
+    }
+}
+
+/// A limit together with the current value it applies to, in the units of their getters
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LimitReading {
+    /// the configured limit
+    pub limit: f32,
+    /// the current value
+    pub value: f32,
+}
+
+impl LimitReading {
+    /// How far the value is below the limit, negative if the limit is exceeded
+    pub fn headroom(&self) -> f32 {
+        self.limit - self.value
+    }
+
+    /// The value as a fraction of the limit, `None` if the limit is not positive
+    pub fn utilization(&self) -> Option<f32> {
+        (self.limit > 0.0).then(|| self.value / self.limit)
+    }
+}
+
+impl RyzenAdj {
+    /// Gets the APU PPT slow limit and value of A+A dGPU platforms, in W
+    ///
+    /// ryzenadj does not expose an APU fast limit, so there is no matching `apu_fast`
+    pub fn apu_slow(&self) -> RyzenAdjResult<LimitReading> {
+        Ok(LimitReading {
+            limit: self.get_apu_slow_limit()?,
+            value: self.get_apu_slow_value()?,
+        })
+    }
+
+    /// Gets the APU skin temperature limit and value, in degree C
+    pub fn apu_skin_temp(&self) -> RyzenAdjResult<LimitReading> {
+        Ok(LimitReading {
+            limit: self.get_apu_skin_temp_limit()?,
+            value: self.get_apu_skin_temp_value()?,
+        })
+    }
+
+    /// Gets the skin temperature power limit, in W
+    ///
+    /// ryzenadj can not read back the limit, so this returns the last value set through
+    /// [`RyzenAdj::set_skin_temp_power_limit`] on this instance and `None` if none was set
+    pub fn skin_temp_power(&self) -> RyzenAdjResult<Option<f32>> {
+        Ok(self
+            .skin_temp_power_limit
+            .get()
+            .map(|limit| limit as f32 / 1000.0))
+    }
+
+    /// Gets the dgpu skin temperature limit and value, in degree C
+    pub fn dgpu_skin_temp(&self) -> RyzenAdjResult<LimitReading> {
+        Ok(LimitReading {
+            limit: self.get_dgpu_skin_temp_limit()?,
+            value: self.get_dgpu_skin_temp_value()?,
+        })
+    }
+
+    /// Gets the socket power paired with the fast PPT limit, in W
+    ///
+    /// The fast limit is the one capping instantaneous package power, the STAPM and slow limits
+    /// only apply to averages over time
+    pub fn socket_power(&self) -> RyzenAdjResult<LimitReading> {
+        Ok(LimitReading {
+            limit: self.get_fast_limit()?,
+            value: self.get_socket_power()?,
+        })
+    }
+
+    /// Gets the socket power as a fraction of the fast PPT limit, see [`RyzenAdj::socket_power`]
+    ///
+    /// Returns [`RyzenAdjError::GetNaN`] if the fast limit is not positive
+    pub fn socket_power_utilization(&self) -> RyzenAdjResult<f32> {
+        self.socket_power()?
+            .utilization()
+            .ok_or(RyzenAdjError::GetNaN)
+    }
+
+    /// Gets the cclk busy value as a percentage of the cclk setpoint
+    ///
+    /// The setpoint is the core clock the SMU currently targets within the active limits, the busy value
+    /// how much of it the cores actually use, so 100% means the cores are clock bound. Returns
+    /// [`RyzenAdjError::GetNaN`] if the setpoint is not positive.
+    pub fn cclk_utilization(&self) -> RyzenAdjResult<f32> {
+        let busy = self.get_cclk_busy_value()?;
+        let setpoint = self.get_cclk_setpoint()?;
+        if setpoint > 0.0 {
+            Ok(busy / setpoint * 100.0)
+        } else {
+            Err(RyzenAdjError::GetNaN)
+        }
+    }
+}
+
+/// Weights of the tctl, APU skin temperature and socket power utilization in [`RyzenAdj::throttle_risk`]
+///
+/// tctl weighs double as it is the limit the SMU throttles hardest on, the weights add up to 1
+pub const THROTTLE_RISK_WEIGHTS: [f32; 3] = [0.5, 0.25, 0.25];
+
+impl RyzenAdj {
+    /// Gets a 0..=1 score of how close the CPU is to being throttled, higher is closer
+    ///
+    /// The score is the average of the utilizations (value divided by limit, clamped to 0..=1) of
+    /// the tctl temperature, the APU skin temperature and the socket power against the fast PPT
+    /// limit, weighted by [`THROTTLE_RISK_WEIGHTS`]. Utilizations the CPU does not report are left
+    /// out and the remaining weights scaled up. Returns [`RyzenAdjError::GetNaN`] if none is reported.
+    pub fn throttle_risk(&self) -> RyzenAdjResult<f32> {
+        let tctl = || {
+            Ok(LimitReading {
+                limit: self.get_tctl_temp()?,
+                value: self.get_tctl_temp_value()?,
+            })
+        };
+        let readings = [tctl(), self.apu_skin_temp(), self.socket_power()];
+
+        let mut risk = 0.0;
+        let mut weights = 0.0;
+        for (reading, weight) in readings.into_iter().zip(THROTTLE_RISK_WEIGHTS) {
+            let utilization = match reading {
+                Ok(reading) => reading.utilization(),
+                Err(RyzenAdjError::GetNaN) => None,
+                Err(err) => return Err(err),
+            };
+            if let Some(utilization) = utilization {
+                risk += utilization.clamp(0.0, 1.0) * weight;
+                weights += weight;
+            }
+        }
+        if weights > 0.0 {
+            Ok(risk / weights)
+        } else {
+            Err(RyzenAdjError::GetNaN)
+        }
+    }
+}
+
+/// Where a temperature in a [`TemperatureSummary`] was read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TemperatureSource {
+    /// tctl temp value
+    Tctl,
+    /// gfx temp
+    Gfx,
+    /// l3 temp
+    L3,
+    /// APU skin temperature value
+    ApuSkin,
+    /// dgpu skin temp value
+    DgpuSkin,
+    /// core temp of the given core
+    Core(u32),
+}
+
+/// All temperatures and temperature limits in degree C plus the skin temperature power limit, returned by [`RyzenAdj::temperatures`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemperatureSummary {
+    /// tctl temp value
+    pub tctl: Option<f32>,
+    /// gfx temp
+    pub gfx: Option<f32>,
+    /// l3 temp
+    pub l3: Option<f32>,
+    /// APU skin temperature value
+    pub apu_skin: Option<f32>,
+    /// dgpu skin temp value
+    pub dgpu_skin: Option<f32>,
+    /// APU skin temperature limit
+    pub apu_skin_limit: Option<f32>,
+    /// dgpu skin temp limit
+    pub dgpu_skin_limit: Option<f32>,
+    /// skin temperature power limit in W, see [`RyzenAdj::skin_temp_power`]
+    pub skin_temp_power_limit: Option<f32>,
+    /// core temps, indexed by core
+    pub cores: Vec<Option<f32>>,
+}
+
+impl TemperatureSummary {
+    /// APU skin temperature together with its limit, `None` if any of them is not reported
+    pub fn apu_skin_temp(&self) -> Option<LimitReading> {
+        Some(LimitReading {
+            limit: self.apu_skin_limit?,
+            value: self.apu_skin?,
+        })
+    }
+
+    /// dgpu skin temperature together with its limit, `None` if any of them is not reported
+    pub fn dgpu_skin_temp(&self) -> Option<LimitReading> {
+        Some(LimitReading {
+            limit: self.dgpu_skin_limit?,
+            value: self.dgpu_skin?,
+        })
+    }
+
+    /// Iterates over all available temperatures together with their source, limits are not included
+    pub fn iter(&self) -> impl Iterator<Item = (TemperatureSource, f32)> + '_ {
+        [
+            (TemperatureSource::Tctl, self.tctl),
+            (TemperatureSource::Gfx, self.gfx),
+            (TemperatureSource::L3, self.l3),
+            (TemperatureSource::ApuSkin, self.apu_skin),
+            (TemperatureSource::DgpuSkin, self.dgpu_skin),
+        ]
+        .into_iter()
+        .chain(
+            self.cores
+                .iter()
+                .enumerate()
+                .map(|(core, temp)| (TemperatureSource::Core(core as u32), *temp)),
+        )
+        .filter_map(|(source, temp)| temp.map(|temp| (source, temp)))
+    }
+
+    /// Returns the highest available temperature and where it was read from
+    pub fn hottest(&self) -> Option<(TemperatureSource, f32)> {
+        self.iter().max_by(|(_, a), (_, b)| a.total_cmp(b))
+    }
+}
+
+impl RyzenAdj {
+    /// Gets all temperatures at once, the current tctl value is used instead of its limit
+    pub fn temperatures(&self) -> RyzenAdjResult<TemperatureSummary> {
+        let mut cores = Vec::with_capacity(MAX_CORES as usize);
+        for core in 0..MAX_CORES {
+            cores.push(available(self.get_core_temp(core))?);
+        }
+        while cores.last() == Some(&None) {
+            cores.pop();
+        }
+
+        Ok(TemperatureSummary {
+            tctl: available(self.get_tctl_temp_value())?,
+            gfx: available(self.get_gfx_temp())?,
+            l3: available(self.get_l3_temp())?,
+            apu_skin: available(self.get_apu_skin_temp_value())?,
+            dgpu_skin: available(self.get_dgpu_skin_temp_value())?,
+            apu_skin_limit: available(self.get_apu_skin_temp_limit())?,
+            dgpu_skin_limit: available(self.get_dgpu_skin_temp_limit())?,
+            skin_temp_power_limit: self.skin_temp_power()?,
+            cores,
+        })
+    }
+}
+
+/// Aggregated core temperatures in degree C, returned by [`RyzenAdj::core_temp_stats`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CoreTempStats {
+    /// average over all cores reporting a temperature
+    pub avg: f32,
+    /// temperature of the hottest core
+    pub max: f32,
+    /// index of the hottest core, the first one if several are equally hot
+    pub max_core_index: u32,
+    /// temperature of the coolest core
+    pub min: f32,
+}
+
+impl RyzenAdj {
+    /// Gets the average, hottest and coolest core temperature, cores not reporting a temperature are skipped
+    ///
+    /// Returns [`RyzenAdjError::GetNaN`] if no core reports a temperature
+    pub fn core_temp_stats(&self) -> RyzenAdjResult<CoreTempStats> {
+        let mut stats: Option<CoreTempStats> = None;
+        let mut sum = 0.0;
+        let mut cores = 0;
+        for core in 0..MAX_CORES {
+            let Some(temp) = available(self.get_core_temp(core))? else {
+                continue;
+            };
+            sum += temp;
+            cores += 1;
+            let stats = stats.get_or_insert(CoreTempStats {
+                avg: temp,
+                max: temp,
+                max_core_index: core,
+                min: temp,
+            });
+            if temp > stats.max {
+                stats.max = temp;
+                stats.max_core_index = core;
+            }
+            stats.min = stats.min.min(temp);
+        }
+        let mut stats = stats.ok_or(RyzenAdjError::GetNaN)?;
+        stats.avg = sum / cores as f32;
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temperatures() -> TemperatureSummary {
+        TemperatureSummary {
+            tctl: Some(70.0),
+            gfx: Some(55.0),
+            l3: None,
+            apu_skin: Some(40.0),
+            dgpu_skin: None,
+            apu_skin_limit: Some(95.0),
+            dgpu_skin_limit: Some(90.0),
+            skin_temp_power_limit: Some(120.0),
+            cores: vec![Some(68.0), None, Some(66.5)],
+        }
+    }
+
+    #[test]
+    fn hottest_of_nothing_is_none() {
+        let empty = TemperatureSummary {
+            tctl: None,
+            gfx: None,
+            l3: None,
+            apu_skin: None,
+            dgpu_skin: None,
+            apu_skin_limit: Some(95.0),
+            dgpu_skin_limit: None,
+            skin_temp_power_limit: None,
+            cores: vec![None, None],
+        };
+        assert_eq!(empty.hottest(), None);
+    }
+
+    #[test]
+    fn hottest_ignores_limits() {
+        assert_eq!(
+            temperatures().hottest(),
+            Some((TemperatureSource::Tctl, 70.0))
+        );
+    }
+
+    #[test]
+    fn hottest_labels_cores_by_index() {
+        let mut summary = temperatures();
+        summary.cores[2] = Some(81.0);
+        assert_eq!(summary.hottest(), Some((TemperatureSource::Core(2), 81.0)));
+        summary.dgpu_skin = Some(85.0);
+        assert_eq!(summary.hottest(), Some((TemperatureSource::DgpuSkin, 85.0)));
+    }
+}
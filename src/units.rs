@@ -0,0 +1,315 @@
+use crate::limits;
+use crate::{RyzenAdjError, RyzenAdjResult};
+
+/// Clock frequency in MHz, accepted by the `_mhz` setters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Megahertz(pub u32);
+
+impl Megahertz {
+    /// Highest frequency the `_mhz` setters will pass on to the SMU
+    pub const MAX: Megahertz = Megahertz(5000);
+
+    /// Returns the raw MHz value if it is in the plausible 0..=5000 range
+    pub(crate) fn checked(self) -> RyzenAdjResult<u32> {
+        limits::CLOCK_MHZ.check(self.0.into())?;
+        Ok(self.0)
+    }
+}
+
+impl From<u32> for Megahertz {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+/// Current in mA, as taken by the `_current` setters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Milliamps(pub u32);
+
+impl Milliamps {
+    /// Highest current the `_amps` setters will pass on to the SMU
+    pub const MAX: Milliamps = Milliamps(250_000);
+}
+
+impl From<u32> for Milliamps {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Milliamps> for Amps {
+    fn from(value: Milliamps) -> Self {
+        Self(value.0 as f32 / 1000.0)
+    }
+}
+
+/// Current in A, as shown by most BIOS setup screens and accepted by the `_amps` setters
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Amps(pub f32);
+
+impl Amps {
+    /// Converts to mA rounded to the nearest mA
+    ///
+    /// Returns `None` if the current is negative, not a number or does not fit into a `u32`
+    pub fn to_milliamps(self) -> Option<Milliamps> {
+        let milliamps = (self.0 * 1000.0).round();
+        // NaN fails both comparisons
+        (milliamps >= 0.0 && milliamps <= u32::MAX as f32).then_some(Milliamps(milliamps as u32))
+    }
+
+    /// Returns the raw mA value if it is in the plausible 0..=250A range
+    pub(crate) fn checked(self) -> RyzenAdjResult<u32> {
+        if !self.0.is_finite() {
+            return Err(RyzenAdjError::ValueNotFinite { value: self.0 });
+        }
+        let milliamps = self
+            .to_milliamps()
+            .ok_or(RyzenAdjError::AdjValueOutOfRange {
+                value: (self.0 * 1000.0) as i64,
+                min: limits::CURRENT_MA.min,
+                max: limits::CURRENT_MA.max,
+            })?;
+        limits::CURRENT_MA.check(milliamps.0.into())?;
+        Ok(milliamps.0)
+    }
+}
+
+impl From<f32> for Amps {
+    fn from(value: f32) -> Self {
+        Self(value)
+    }
+}
+
+/// Scale of the power values reported by ryzenadj, returned by [`RyzenAdj::power_scale`](crate::RyzenAdj::power_scale)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PowerScale {
+    /// values are in W, as documented by ryzenadj
+    Watts,
+    /// values are in mW
+    Milliwatts,
+}
+
+/// Power readings above this many units can only be mW, no supported APU draws 1kW
+pub(crate) const POWER_SCALE_THRESHOLD: f32 = 1000.0;
+
+/// Guesses the power scale from the fast PPT limit, or the socket power if the limit is NaN
+///
+/// Returns the scale and if it is certain: a limit is never set as low as 1000 mW, but an idle
+/// socket can draw less than that, so a low socket power only suggests W. `None` if both are NaN.
+pub(crate) fn guess_power_scale(fast_limit: f32, socket_power: f32) -> Option<(PowerScale, bool)> {
+    let (reading, from_limit) = if fast_limit.is_nan() {
+        (socket_power, false)
+    } else {
+        (fast_limit, true)
+    };
+    if reading.is_nan() {
+        None
+    } else if reading > POWER_SCALE_THRESHOLD {
+        Some((PowerScale::Milliwatts, true))
+    } else {
+        Some((PowerScale::Watts, from_limit))
+    }
+}
+
+const VID_BASE_VOLTS: f32 = 1.55;
+const VID_STEP_VOLTS: f32 = 0.00625;
+
+/// Highest valid VID, encodes 0V
+pub const MAX_VID: u32 = 248;
+
+/// Converts a voltage to the VID expected by `set_oc_volt`, rounding to the nearest step
+///
+/// Returns `None` if the voltage is outside of 0V..=1.55V
+pub fn volts_to_vid(volts: f32) -> Option<u32> {
+    if (0.0..=VID_BASE_VOLTS).contains(&volts) {
+        let vid = ((VID_BASE_VOLTS - volts) / VID_STEP_VOLTS).round() as u32;
+        Some(vid.min(MAX_VID))
+    } else {
+        None
+    }
+}
+
+/// Converts a voltage with [`volts_to_vid`], failing with the error returned by `set_oc_volt_v`
+pub(crate) fn checked_vid(volts: f32) -> RyzenAdjResult<u32> {
+    if !volts.is_finite() {
+        return Err(RyzenAdjError::ValueNotFinite { value: volts });
+    }
+    volts_to_vid(volts).ok_or(RyzenAdjError::AdjValueOutOfRange {
+        value: (volts * 1000.0).round() as i64,
+        min: 0,
+        max: 1550,
+    })
+}
+
+/// Converts a VID as used by `set_oc_volt` back to volts
+///
+/// Returns `None` if the VID is above [`MAX_VID`]
+pub fn vid_to_volts(vid: u32) -> Option<f32> {
+    if vid <= MAX_VID {
+        // MAX_VID would be slightly below 0V with f32 rounding
+        Some((VID_BASE_VOLTS - vid as f32 * VID_STEP_VOLTS).max(0.0))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn power_scale_is_certain_from_a_limit_or_a_high_reading() {
+        assert_eq!(
+            guess_power_scale(25.0, 800.0),
+            Some((PowerScale::Watts, true))
+        );
+        assert_eq!(
+            guess_power_scale(25000.0, f32::NAN),
+            Some((PowerScale::Milliwatts, true))
+        );
+        assert_eq!(
+            guess_power_scale(f32::NAN, 8000.0),
+            Some((PowerScale::Milliwatts, true))
+        );
+        assert_eq!(guess_power_scale(f32::NAN, f32::NAN), None);
+    }
+
+    #[test]
+    fn low_socket_power_is_an_uncertain_guess() {
+        // 800 mW at idle looks like 800 W would, so W is only assumed
+        assert_eq!(
+            guess_power_scale(f32::NAN, 800.0),
+            Some((PowerScale::Watts, false))
+        );
+        assert_eq!(
+            guess_power_scale(f32::NAN, 12.5),
+            Some((PowerScale::Watts, false))
+        );
+    }
+
+    #[test]
+    fn megahertz_accepts_plausible_range() {
+        assert_eq!(Megahertz(0).checked().unwrap(), 0);
+        assert_eq!(Megahertz(1800).checked().unwrap(), 1800);
+        assert_eq!(Megahertz::MAX.checked().unwrap(), 5000);
+    }
+
+    #[test]
+    fn megahertz_rejects_values_above_max() {
+        for mhz in [5001, 1_800_000, u32::MAX] {
+            assert!(matches!(
+                Megahertz(mhz).checked(),
+                Err(RyzenAdjError::AdjValueOutOfRange {
+                    min: 0,
+                    max: 5000,
+                    ..
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn volts_to_vid_endpoints() {
+        assert_eq!(volts_to_vid(1.55), Some(0));
+        assert_eq!(volts_to_vid(0.0), Some(MAX_VID));
+    }
+
+    #[test]
+    fn volts_to_vid_rounds_to_nearest_step() {
+        // 1.2V is exactly 56 steps below 1.55V
+        assert_eq!(volts_to_vid(1.2), Some(56));
+        // a bit less than half a step either way
+        assert_eq!(volts_to_vid(1.2 + 0.003), Some(56));
+        assert_eq!(volts_to_vid(1.2 - 0.003), Some(56));
+        // a bit more than half a step
+        assert_eq!(volts_to_vid(1.2 - 0.0033), Some(57));
+        assert_eq!(volts_to_vid(1.2 + 0.0033), Some(55));
+    }
+
+    #[test]
+    fn volts_to_vid_rejects_out_of_range() {
+        for volts in [
+            -0.001,
+            1.551,
+            2.0,
+            f32::NAN,
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+        ] {
+            assert_eq!(volts_to_vid(volts), None, "{volts}");
+        }
+    }
+
+    #[test]
+    fn vid_to_volts_endpoints() {
+        assert_eq!(vid_to_volts(0), Some(1.55));
+        assert_eq!(vid_to_volts(MAX_VID), Some(0.0));
+        assert_eq!(vid_to_volts(MAX_VID + 1), None);
+        assert_eq!(vid_to_volts(u32::MAX), None);
+    }
+
+    #[test]
+    fn vid_round_trips_through_volts() {
+        for vid in 0..=MAX_VID {
+            let volts = vid_to_volts(vid).unwrap();
+            assert_eq!(volts_to_vid(volts), Some(vid), "{vid} -> {volts}V");
+        }
+    }
+
+    #[test]
+    fn checked_vid_rejects_non_finite_volts() {
+        assert_eq!(checked_vid(1.2).unwrap(), 56);
+        assert!(matches!(
+            checked_vid(1.6),
+            Err(RyzenAdjError::AdjValueOutOfRange {
+                value: 1600,
+                min: 0,
+                max: 1550
+            })
+        ));
+        for volts in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            assert!(matches!(
+                checked_vid(volts),
+                Err(RyzenAdjError::ValueNotFinite { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn amps_convert_to_milliamps() {
+        assert_eq!(Amps(0.0).to_milliamps(), Some(Milliamps(0)));
+        assert_eq!(Amps(95.0).to_milliamps(), Some(Milliamps(95_000)));
+        assert_eq!(Amps(1.2345).to_milliamps(), Some(Milliamps(1235)));
+        assert_eq!(Amps(0.0004).to_milliamps(), Some(Milliamps(0)));
+        assert_eq!(Amps(-0.001).to_milliamps(), None);
+        assert_eq!(Amps(f32::NAN).to_milliamps(), None);
+        assert_eq!(Amps(1e10).to_milliamps(), None);
+    }
+
+    #[test]
+    fn milliamps_convert_to_amps() {
+        assert_eq!(Amps::from(Milliamps(95_000)), Amps(95.0));
+        assert_eq!(Amps::from(Milliamps(500)), Amps(0.5));
+        let amps = Amps::from(Milliamps(1234));
+        assert_eq!(amps.to_milliamps(), Some(Milliamps(1234)));
+    }
+
+    #[test]
+    fn amps_checked_range() {
+        assert_eq!(Amps(250.0).checked().unwrap(), 250_000);
+        assert!(matches!(
+            Amps(250.001).checked(),
+            Err(RyzenAdjError::AdjValueOutOfRange { value: 250_001, .. })
+        ));
+        assert!(matches!(
+            Amps(-1.0).checked(),
+            Err(RyzenAdjError::AdjValueOutOfRange { value: -1000, .. })
+        ));
+        for amps in [f32::NAN, f32::INFINITY] {
+            assert!(matches!(
+                Amps(amps).checked(),
+                Err(RyzenAdjError::ValueNotFinite { .. })
+            ));
+        }
+    }
+}